@@ -2,6 +2,8 @@ pub mod execute;
 pub mod instantiate;
 pub mod query;
 
+use std::fmt::Display;
+
 use cosmwasm_schema::cw_serde;
 use kujira_std::Denom;
 
@@ -14,14 +16,8 @@ pub struct DenomInfo {
     pub oracle: String,
 }
 
-impl ToString for DenomInfo {
-    fn to_string(&self) -> String {
-        self.denom.to_string()
-    }
-}
-
-impl ToString for &DenomInfo {
-    fn to_string(&self) -> String {
-        self.denom.to_string()
+impl Display for DenomInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.denom.fmt(f)
     }
 }
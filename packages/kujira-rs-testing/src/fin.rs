@@ -1,17 +1,24 @@
-use std::convert::TryFrom;
+use std::{
+    collections::BTreeMap,
+    convert::{TryFrom, TryInto},
+};
 
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 
 use cosmwasm_std::{
-    coins, to_json_binary, Addr, BankMsg, Binary, CosmosMsg, Decimal256, Deps, DepsMut, Env,
-    Fraction, MessageInfo, Response, StdError, StdResult, Uint128, Uint256, WasmMsg,
+    to_json_binary, Addr, BankMsg, Binary, Coin, CosmosMsg, Decimal256, Deps, DepsMut, Empty, Env,
+    MessageInfo, Order as SortOrder, Response, StdError, StdResult, Storage, Timestamp, Uint128,
+    Uint256,
 };
 use cw20::Denom;
-use cw_storage_plus::{Item, Map};
+use cw_storage_plus::{Bound, Item, Map};
 use kujira::{
-    fin::{ExecuteMsg, InstantiateMsg, NewOrderData, OrderResponse, QueryMsg},
-    KujiraMsg, KujiraQuery,
+    fin::{
+        BookResponse, ConfigResponse, ExecuteMsg, InstantiateMsg, NewOrderData, OrderResponse,
+        OrdersResponse, PoolResponse, PriceResponse, QueryMsg, SimulationResponse,
+    },
+    AssetInfo, CallbackData, KujiraMsg, KujiraQuery, Precision, PrecisionError, TickSize,
 };
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
@@ -19,7 +26,10 @@ use serde::{Deserialize, Serialize};
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum MockMsg {
+    /// Fills `amount` of the order's offer denom at the order price, as though
+    /// a counterparty outside of the book had taken it
     PartialFill { idx: Uint128, amount: Uint128 },
+    /// Fills the whole remaining order at the order price
     Fill { idx: Uint128 },
 }
 
@@ -30,28 +40,304 @@ pub enum MockExecuteMsg {
     Mock(MockMsg),
 }
 
-const STABLE: &str = "factory/contract0/uusk";
-const COLLATERAL: &str = "factory/owner/coll";
+/// Orders offering `denoms[0]`, ie asks
+const BASE: u8 = 0;
+/// Orders offering `denoms[1]`, ie bids
+const QUOTE: u8 = 1;
 
-const CUR_ORDER_IDX: Item<Uint128> = Item::new("cur_order_idx");
+const DEFAULT_LIMIT: u8 = 30;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+struct Config {
+    owner: Addr,
+    denoms: [String; 2],
+    decimal_delta: i8,
+    price_precision: Precision,
+    is_bootstrapping: bool,
+    fee_taker: Decimal256,
+    fee_maker: Decimal256,
+    fee_address: Addr,
+}
+
+impl Config {
+    fn side(&self, denom: &str) -> StdResult<u8> {
+        match self.denoms.iter().position(|d| d == denom) {
+            Some(0) => Ok(BASE),
+            Some(_) => Ok(QUOTE),
+            None => Err(StdError::generic_err("Invalid Denom")),
+        }
+    }
+
+    fn validate_price(&self, price: &Decimal256) -> StdResult<()> {
+        TickSize::new(self.price_precision.clone(), self.decimal_delta)
+            .validate(price)
+            .map_err(|e| match e {
+                PrecisionError::Zero {} => StdError::generic_err("Price must be greater than zero"),
+                PrecisionError::Imprecise {} => StdError::generic_err("Invalid price precision"),
+                e => StdError::generic_err(e.to_string()),
+            })
+    }
+}
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 struct Order {
-    price: Decimal256,
-    denom: String,
-    amount: Uint128,
-    filled: Uint128,
     owner: Addr,
+    price: Decimal256,
+    side: u8,
+    offer_amount: Uint256,
+    filled_amount: Uint256,
+    original_offer_amount: Uint256,
+    created_at: Timestamp,
 }
+
+/// All resting orders at a single price on one side of the book, in time priority
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+struct Pool {
+    total: Uint256,
+    orders: Vec<u128>,
+}
+
+const CONFIG: Item<Config> = Item::new("config");
+const CUR_ORDER_IDX: Item<Uint128> = Item::new("cur_order_idx");
 const ORDERS: Map<u128, Order> = Map::new("orders");
+const USER_ORDERS: Map<(&Addr, u128), ()> = Map::new("user_orders");
+const POOLS: Map<(u8, Vec<u8>), Pool> = Map::new("pools");
+
+fn price_key(price: &Decimal256) -> Vec<u8> {
+    price.atomics().to_be_bytes().to_vec()
+}
+
+fn key_price(key: &[u8]) -> StdResult<Decimal256> {
+    let bytes: [u8; 32] = key
+        .try_into()
+        .map_err(|_| StdError::generic_err("Invalid price key"))?;
+    Ok(Decimal256::new(Uint256::from_be_bytes(bytes)))
+}
+
+fn other(side: u8) -> u8 {
+    1 - side
+}
+
+/// Converts an amount of the `side` denom into the other denom of the pair
+fn convert(amount: Uint256, price: Decimal256, side: u8, ceil: bool) -> Uint256 {
+    match (side, ceil) {
+        (BASE, false) => amount.mul_floor(price),
+        (BASE, true) => amount.mul_ceil(price),
+        (_, false) => amount.div_floor(price),
+        (_, true) => amount.div_ceil(price),
+    }
+}
+
+/// A single maker order matched against an incoming offer
+struct Fill {
+    idx: u128,
+    /// Amount of the maker's offer denom consumed
+    take: Uint256,
+    /// Amount of the taker's offer denom credited to the maker
+    give: Uint256,
+}
+
+#[derive(Default)]
+struct Execution {
+    fills: Vec<Fill>,
+    /// Amount of the taker's offer consumed by the book
+    offer_amount: Uint256,
+    /// Amount returned to the taker, before fees
+    return_amount: Uint256,
+    /// Difference between the return at the best price and the actual return
+    spread_amount: Uint256,
+}
+
+/// Walks the opposing side of the book in price-time priority, without
+/// writing any state. If `limit` is provided, orders priced worse than it
+/// for the taker are not matched
+fn simulate(
+    storage: &dyn Storage,
+    side: u8,
+    amount: Uint256,
+    limit: Option<Decimal256>,
+) -> StdResult<Execution> {
+    let maker_side = other(side);
+    // Bids are matched from the highest price, asks from the lowest
+    let order = if maker_side == QUOTE {
+        SortOrder::Descending
+    } else {
+        SortOrder::Ascending
+    };
+
+    let mut exec = Execution::default();
+    let mut remaining = amount;
+    let mut best_price = None;
+    let mut dust = false;
+
+    for item in POOLS.prefix(maker_side).range(storage, None, None, order) {
+        let (key, pool) = item?;
+        let price = key_price(&key)?;
+        let crosses = match (limit, maker_side) {
+            (None, _) => true,
+            (Some(limit), QUOTE) => price >= limit,
+            (Some(limit), _) => price <= limit,
+        };
+        if !crosses {
+            break;
+        }
+        best_price.get_or_insert(price);
+
+        for idx in pool.orders {
+            if remaining.is_zero() {
+                break;
+            }
+            let maker = ORDERS.load(storage, idx)?;
+            let capacity = convert(remaining, price, side, false);
+            let (take, give) = if capacity >= maker.offer_amount {
+                let take = maker.offer_amount;
+                (take, convert(take, price, maker_side, true).min(remaining))
+            } else {
+                (capacity, remaining)
+            };
+            if take.is_zero() {
+                // Whatever remains is dust that can't buy a single unit
+                dust = true;
+                break;
+            }
+            remaining -= give;
+            exec.offer_amount += give;
+            exec.return_amount += take;
+            exec.fills.push(Fill { idx, take, give });
+        }
+
+        if remaining.is_zero() || dust {
+            break;
+        }
+    }
+
+    if let Some(price) = best_price {
+        exec.spread_amount =
+            convert(exec.offer_amount, price, side, false).saturating_sub(exec.return_amount);
+    }
+    Ok(exec)
+}
+
+/// Commits a simulated execution to the book
+fn apply(storage: &mut dyn Storage, exec: &Execution) -> StdResult<()> {
+    for fill in exec.fills.iter() {
+        let mut order = ORDERS.load(storage, fill.idx)?;
+        order.offer_amount -= fill.take;
+        order.filled_amount += fill.give;
+        ORDERS.save(storage, fill.idx, &order)?;
+        reduce_pool(storage, fill.idx, &order, fill.take)?;
+    }
+    Ok(())
+}
+
+fn reduce_pool(
+    storage: &mut dyn Storage,
+    idx: u128,
+    order: &Order,
+    amount: Uint256,
+) -> StdResult<()> {
+    let key = (order.side, price_key(&order.price));
+    let mut pool = POOLS.load(storage, key.clone())?;
+    pool.total = pool.total.checked_sub(amount)?;
+    if order.offer_amount.is_zero() {
+        pool.orders.retain(|x| x != &idx);
+    }
+    if pool.orders.is_empty() {
+        POOLS.remove(storage, key);
+    } else {
+        POOLS.save(storage, key, &pool)?;
+    }
+    Ok(())
+}
+
+fn to_coins(config: &Config, amounts: &BTreeMap<u8, Uint256>) -> StdResult<Vec<Coin>> {
+    let mut coins = amounts
+        .iter()
+        .filter(|(_, amount)| !amount.is_zero())
+        .map(|(side, amount)| {
+            Ok(Coin::new(
+                Uint128::try_from(*amount)?,
+                config.denoms[*side as usize].clone(),
+            ))
+        })
+        .collect::<StdResult<Vec<Coin>>>()?;
+    coins.sort_by(|a, b| a.denom.cmp(&b.denom));
+    Ok(coins)
+}
+
+fn send(
+    to: &Addr,
+    funds: Vec<Coin>,
+    callback: Option<CallbackData>,
+) -> StdResult<Option<CosmosMsg<KujiraMsg>>> {
+    match callback {
+        Some(cb) => Ok(Some(cb.to_message(to, Empty {}, funds)?)),
+        None if funds.is_empty() => Ok(None),
+        None => Ok(Some(CosmosMsg::Bank(BankMsg::Send {
+            to_address: to.to_string(),
+            amount: funds,
+        }))),
+    }
+}
+
+fn offer(config: &Config, info: &MessageInfo) -> StdResult<(u8, Uint256)> {
+    match info.funds.as_slice() {
+        [coin] if !coin.amount.is_zero() => Ok((config.side(&coin.denom)?, coin.amount.into())),
+        _ => Err(StdError::generic_err("Exactly one coin must be provided")),
+    }
+}
+
+/// Removes the filled amount from an order, returning the amount owed to the
+/// owner and the maker fee
+fn claim(config: &Config, order: &mut Order) -> (Uint256, Uint256) {
+    let fee = order.filled_amount.mul_floor(config.fee_maker);
+    let amount = order.filled_amount - fee;
+    order.filled_amount = Uint256::zero();
+    (amount, fee)
+}
+
+fn save_or_remove(storage: &mut dyn Storage, idx: u128, order: &Order) -> StdResult<()> {
+    if order.offer_amount.is_zero() && order.filled_amount.is_zero() {
+        ORDERS.remove(storage, idx);
+        USER_ORDERS.remove(storage, (&order.owner, idx));
+        Ok(())
+    } else {
+        ORDERS.save(storage, idx, order)
+    }
+}
+
+fn fee_msg(
+    config: &Config,
+    fees: &BTreeMap<u8, Uint256>,
+) -> StdResult<Option<CosmosMsg<KujiraMsg>>> {
+    send(&config.fee_address, to_coins(config, fees)?, None)
+}
+
+fn native(denom: &Denom) -> StdResult<String> {
+    match denom {
+        Denom::Native(x) => Ok(x.clone()),
+        Denom::Cw20(_) => Err(StdError::generic_err("CW20 Unsupported")),
+    }
+}
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
     deps: DepsMut<KujiraQuery>,
     _env: Env,
     _info: MessageInfo,
-    _msg: InstantiateMsg,
+    msg: InstantiateMsg,
 ) -> StdResult<Response<KujiraMsg>> {
+    let config = Config {
+        owner: msg.owner,
+        denoms: [native(&msg.denoms[0])?, native(&msg.denoms[1])?],
+        decimal_delta: msg.decimal_delta.unwrap_or_default(),
+        price_precision: msg.price_precision,
+        is_bootstrapping: true,
+        fee_taker: msg.fee_taker,
+        fee_maker: msg.fee_maker,
+        fee_address: msg.fee_address,
+    };
+    CONFIG.save(deps.storage, &config)?;
     CUR_ORDER_IDX.save(deps.storage, &Uint128::zero())?;
     Ok(Response::new())
 }
@@ -59,108 +345,196 @@ pub fn instantiate(
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn execute(
     deps: DepsMut<KujiraQuery>,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     msg: MockExecuteMsg,
 ) -> StdResult<Response<KujiraMsg>> {
     let sender = info.sender.clone();
+    let mut config = CONFIG.load(deps.storage)?;
     match msg {
+        MockExecuteMsg::FIN(ExecuteMsg::Launch {}) => {
+            if sender != config.owner {
+                return Err(StdError::generic_err("Unauthorized"));
+            }
+            config.is_bootstrapping = false;
+            CONFIG.save(deps.storage, &config)?;
+            Ok(Response::default().add_attribute("action", "fin-launch"))
+        }
+        MockExecuteMsg::FIN(ExecuteMsg::UpdateConfig {
+            owner,
+            price_precision,
+            fee_taker,
+            fee_maker,
+        }) => {
+            if sender != config.owner {
+                return Err(StdError::generic_err("Unauthorized"));
+            }
+            if let Some(owner) = owner {
+                config.owner = owner;
+            }
+            if let Some(price_precision) = price_precision {
+                config.price_precision = price_precision;
+            }
+            if let Some(fee_taker) = fee_taker {
+                config.fee_taker = fee_taker;
+            }
+            if let Some(fee_maker) = fee_maker {
+                config.fee_maker = fee_maker;
+            }
+            CONFIG.save(deps.storage, &config)?;
+            Ok(Response::default().add_attribute("action", "fin-update-config"))
+        }
         MockExecuteMsg::FIN(ExecuteMsg::Swap {
             belief_price,
+            max_spread,
+            to,
             callback,
             ..
         }) => {
-            let coin = info.funds[0].clone();
-            let amount: Uint256 = coin.amount.into();
-            let price = belief_price.unwrap_or_else(|| Decimal256::from_ratio(1425u128, 100u128));
-
-            let (price, return_denom) = match coin.denom.as_str() {
-                STABLE => (
-                    Decimal256::from_ratio(price.denominator(), price.numerator()),
-                    COLLATERAL,
-                ),
-                COLLATERAL => (price, STABLE),
-                _ => return Err(StdError::generic_err("Invalid Denom")),
-            };
+            if config.is_bootstrapping {
+                return Err(StdError::generic_err("Book is bootstrapping"));
+            }
+            let (side, amount) = offer(&config, &info)?;
+            let exec = simulate(deps.storage, side, amount, None)?;
+            if exec.return_amount.is_zero() {
+                return Err(StdError::generic_err("Insufficient liquidity"));
+            }
 
-            let return_amount: Uint128 = Uint128::try_from(amount.mul_floor(price))?;
+            if let Some(max_spread) = max_spread {
+                let expected = match belief_price {
+                    Some(price) => convert(exec.offer_amount, price, side, false),
+                    None => exec.return_amount + exec.spread_amount,
+                };
+                if expected > exec.return_amount
+                    && Decimal256::from_ratio(expected - exec.return_amount, expected) > max_spread
+                {
+                    return Err(StdError::generic_err("Max spread assertion"));
+                }
+            }
+            apply(deps.storage, &exec)?;
 
-            let message = match callback {
-                Some(cb) => CosmosMsg::Wasm(WasmMsg::Execute {
-                    contract_addr: sender.to_string(),
-                    funds: coins(return_amount.u128(), return_denom),
-                    msg: cb.0,
-                }),
-                None => CosmosMsg::Bank(BankMsg::Send {
-                    to_address: sender.to_string(),
-                    amount: coins(return_amount.u128(), return_denom),
-                }),
+            let commission = exec.return_amount.mul_floor(config.fee_taker);
+            let return_amount = exec.return_amount - commission;
+            let return_side = other(side);
+            let recipient = match callback {
+                Some(_) => sender.clone(),
+                None => to.unwrap_or_else(|| sender.clone()),
             };
 
+            let mut messages = vec![];
+            messages.extend(send(
+                &recipient,
+                to_coins(&config, &BTreeMap::from([(return_side, return_amount)]))?,
+                callback,
+            )?);
+            messages.extend(send(
+                &sender,
+                to_coins(
+                    &config,
+                    &BTreeMap::from([(side, amount - exec.offer_amount)]),
+                )?,
+                None,
+            )?);
+            messages.extend(fee_msg(
+                &config,
+                &BTreeMap::from([(return_side, commission)]),
+            )?);
+
             Ok(Response::default()
-                .add_message(message)
-                .add_attribute("action", "fin-swap"))
+                .add_messages(messages)
+                .add_attribute("action", "fin-swap")
+                .add_attribute("offer_amount", exec.offer_amount.to_string())
+                .add_attribute("return_amount", return_amount.to_string())
+                .add_attribute("spread_amount", exec.spread_amount.to_string())
+                .add_attribute("commission_amount", commission.to_string()))
         }
         MockExecuteMsg::FIN(ExecuteMsg::SubmitOrder { price, callback }) => {
+            let (side, amount) = offer(&config, &info)?;
+            config.validate_price(&price)?;
+
             let idx = CUR_ORDER_IDX.load(deps.storage)?;
             CUR_ORDER_IDX.save(deps.storage, &(idx + Uint128::from(1u128)))?;
-            let coin = info.funds[0].clone();
-            let amount = coin.amount;
-            let denom = coin.denom;
+
+            let mut messages = vec![];
+            let mut offer_amount = amount;
+            if !config.is_bootstrapping {
+                let exec = simulate(deps.storage, side, amount, Some(price))?;
+                apply(deps.storage, &exec)?;
+                let commission = exec.return_amount.mul_floor(config.fee_taker);
+                let return_side = other(side);
+                messages.extend(send(
+                    &sender,
+                    to_coins(
+                        &config,
+                        &BTreeMap::from([(return_side, exec.return_amount - commission)]),
+                    )?,
+                    None,
+                )?);
+                messages.extend(fee_msg(
+                    &config,
+                    &BTreeMap::from([(return_side, commission)]),
+                )?);
+                offer_amount -= exec.offer_amount;
+            }
+
             let order = Order {
-                price,
-                amount,
-                denom,
-                filled: Uint128::zero(),
                 owner: sender.clone(),
+                price,
+                side,
+                offer_amount,
+                filled_amount: Uint256::zero(),
+                original_offer_amount: amount,
+                created_at: env.block.time,
             };
-
             ORDERS.save(deps.storage, idx.u128(), &order)?;
+            USER_ORDERS.save(deps.storage, (&sender, idx.u128()), &())?;
+
+            if !offer_amount.is_zero() {
+                POOLS.update(deps.storage, (side, price_key(&price)), |pool| {
+                    let mut pool = pool.unwrap_or_default();
+                    pool.total += offer_amount;
+                    pool.orders.push(idx.u128());
+                    StdResult::Ok(pool)
+                })?;
+            }
 
-            match callback {
-                Some(cb) => Ok(Response::default()
-                    .add_message(cb.to_message(&sender, NewOrderData { idx }, vec![])?)
-                    .add_attribute("order_idx", idx)
-                    .add_attribute("action", "fin-submit-order")),
-                None => Ok(Response::default()
-                    .add_attribute("order_idx", idx)
-                    .add_attribute("action", "fin-submit-order")),
+            if let Some(cb) = callback {
+                messages.push(cb.to_message(&sender, NewOrderData { idx }, vec![])?);
             }
+
+            Ok(Response::default()
+                .add_messages(messages)
+                .add_attribute("order_idx", idx)
+                .add_attribute("action", "fin-submit-order"))
         }
         MockExecuteMsg::FIN(ExecuteMsg::WithdrawOrders {
             order_idxs,
             callback,
         }) => {
-            let mut messages = vec![];
-            for idx in order_idxs.unwrap_or_default() {
-                let mut order = ORDERS.load(deps.storage, idx.u128())?;
+            let idxs = match order_idxs {
+                Some(idxs) => idxs.iter().map(|x| x.u128()).collect(),
+                None => USER_ORDERS
+                    .prefix(&sender)
+                    .keys(deps.storage, None, None, SortOrder::Ascending)
+                    .take(DEFAULT_LIMIT as usize)
+                    .collect::<StdResult<Vec<u128>>>()?,
+            };
+            let mut amounts = BTreeMap::new();
+            let mut fees = BTreeMap::new();
+            for idx in idxs {
+                let mut order = ORDERS.load(deps.storage, idx)?;
                 if order.owner != sender {
                     return Err(StdError::generic_err("Not your order"));
                 }
-                if order.filled.is_zero() {
-                    continue;
-                }
-                let return_denom = match order.denom.as_str() {
-                    STABLE => COLLATERAL,
-                    COLLATERAL => STABLE,
-                    _ => return Err(StdError::generic_err("Invalid Denom")),
-                };
-                let coin = coins(order.filled.u128(), return_denom);
-
-                order.filled = Uint128::zero();
-                ORDERS.save(deps.storage, idx.u128(), &order)?;
-                match callback.clone() {
-                    Some(cb) => messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
-                        contract_addr: sender.to_string(),
-                        funds: coin,
-                        msg: cb.0,
-                    })),
-                    None => messages.push(CosmosMsg::Bank(BankMsg::Send {
-                        to_address: sender.to_string(),
-                        amount: coin,
-                    })),
-                }
+                let (amount, fee) = claim(&config, &mut order);
+                *amounts.entry(other(order.side)).or_default() += amount;
+                *fees.entry(other(order.side)).or_default() += fee;
+                save_or_remove(deps.storage, idx, &order)?;
             }
+
+            let mut messages = vec![];
+            messages.extend(send(&sender, to_coins(&config, &amounts)?, callback)?);
+            messages.extend(fee_msg(&config, &fees)?);
             Ok(Response::default()
                 .add_messages(messages)
                 .add_attribute("action", "fin-withdraw"))
@@ -170,54 +544,38 @@ pub fn execute(
             amount,
             callback,
         }) => {
-            let mut order = ORDERS.load(deps.storage, order_idx.u128())?;
-            if order.owner != sender {
-                return Err(StdError::generic_err("Not your order"));
-            }
-            let amount = amount
-                .map(|a| Uint128::try_from(a).unwrap())
-                .unwrap_or(order.amount);
-            let return_denom = order.denom.clone();
-            let mut coin = coins(amount.u128(), return_denom);
-            order.amount = order.amount.checked_sub(amount)?;
-            ORDERS.save(deps.storage, order_idx.u128(), &order)?;
-            if amount.is_zero() {
-                coin = vec![];
-            }
-            let mut messages = vec![];
-            match callback {
-                Some(cb) => messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
-                    contract_addr: sender.to_string(),
-                    funds: coin,
-                    msg: cb.0,
-                })),
-                None => {
-                    if !coin.is_empty() {
-                        messages.push(CosmosMsg::Bank(BankMsg::Send {
-                            to_address: sender.to_string(),
-                            amount: coin,
-                        }))
-                    }
-                }
-            };
-
+            let messages = retract(
+                deps.storage,
+                &config,
+                &sender,
+                &[(order_idx.u128(), amount)],
+                callback,
+            )?;
             Ok(Response::default()
                 .add_messages(messages)
                 .add_attribute("action", "fin-retract"))
         }
+        MockExecuteMsg::FIN(ExecuteMsg::RetractOrders {
+            order_idxs,
+            callback,
+        }) => {
+            let idxs: Vec<(u128, Option<Uint256>)> =
+                order_idxs.iter().map(|x| (x.u128(), None)).collect();
+            let messages = retract(deps.storage, &config, &sender, &idxs, callback)?;
+            Ok(Response::default()
+                .add_messages(messages)
+                .add_attribute("action", "fin-retract"))
+        }
+        MockExecuteMsg::FIN(ExecuteMsg::Prune { .. }) => {
+            Ok(Response::default().add_attribute("action", "fin-prune"))
+        }
         MockExecuteMsg::Mock(MockMsg::PartialFill { idx, amount }) => {
             let mut order = ORDERS.load(deps.storage, idx.u128())?;
-            let price = match order.denom.as_str() {
-                STABLE => {
-                    Decimal256::from_ratio(order.price.denominator(), order.price.numerator())
-                }
-                COLLATERAL => order.price,
-                _ => return Err(StdError::generic_err("Invalid Denom")),
-            };
-            let return_amount: Uint128 = Uint128::try_from(Uint256::from(amount).mul_floor(price))?;
-            order.amount = order.amount.checked_sub(amount)?;
-            order.filled += return_amount;
+            let amount = Uint256::from(amount);
+            order.offer_amount = order.offer_amount.checked_sub(amount)?;
+            order.filled_amount += convert(amount, order.price, order.side, false);
             ORDERS.save(deps.storage, idx.u128(), &order)?;
+            reduce_pool(deps.storage, idx.u128(), &order, amount)?;
 
             Ok(Response::default().add_attribute("action", "mock-fill"))
         }
@@ -226,35 +584,175 @@ pub fn execute(
 
             execute(
                 deps,
-                _env,
+                env,
                 info,
                 MockExecuteMsg::Mock(MockMsg::PartialFill {
                     idx,
-                    amount: order.amount,
+                    amount: Uint128::try_from(order.offer_amount)?,
                 }),
             )
         }
-        _ => Ok(Response::default().add_attribute("action", "fin-UNKNOWN-MSG")),
     }
 }
 
+/// Retracts the requested amounts of each order, along with any filled amount
+fn retract(
+    storage: &mut dyn Storage,
+    config: &Config,
+    sender: &Addr,
+    idxs: &[(u128, Option<Uint256>)],
+    callback: Option<CallbackData>,
+) -> StdResult<Vec<CosmosMsg<KujiraMsg>>> {
+    let mut amounts = BTreeMap::new();
+    let mut fees = BTreeMap::new();
+    for (idx, amount) in idxs {
+        let mut order = ORDERS.load(storage, *idx)?;
+        if order.owner != sender {
+            return Err(StdError::generic_err("Not your order"));
+        }
+        let amount = amount.unwrap_or(order.offer_amount);
+        order.offer_amount = order.offer_amount.checked_sub(amount)?;
+        if !amount.is_zero() {
+            reduce_pool(storage, *idx, &order, amount)?;
+        }
+        *amounts.entry(order.side).or_default() += amount;
+
+        let (filled, fee) = claim(config, &mut order);
+        *amounts.entry(other(order.side)).or_default() += filled;
+        *fees.entry(other(order.side)).or_default() += fee;
+        save_or_remove(storage, *idx, &order)?;
+    }
+
+    let mut messages = vec![];
+    messages.extend(send(sender, to_coins(config, &amounts)?, callback)?);
+    messages.extend(fee_msg(config, &fees)?);
+    Ok(messages)
+}
+
+fn order_response(config: &Config, idx: u128, order: Order) -> OrderResponse {
+    OrderResponse {
+        idx: Uint128::from(idx),
+        owner: order.owner,
+        quote_price: order.price,
+        offer_denom: Denom::Native(config.denoms[order.side as usize].clone()),
+        offer_amount: order.offer_amount,
+        filled_amount: order.filled_amount,
+        created_at: order.created_at,
+        original_offer_amount: order.original_offer_amount,
+    }
+}
+
+fn pool_response(config: &Config, side: u8, price: Decimal256, pool: Pool) -> PoolResponse {
+    PoolResponse {
+        quote_price: price,
+        offer_denom: Denom::Native(config.denoms[side as usize].clone()),
+        total_offer_amount: pool.total,
+    }
+}
+
+fn book_side(
+    deps: Deps<KujiraQuery>,
+    config: &Config,
+    side: u8,
+    limit: usize,
+    offset: usize,
+) -> StdResult<Vec<PoolResponse>> {
+    // Both sides are paged out from the spread
+    let order = if side == QUOTE {
+        SortOrder::Descending
+    } else {
+        SortOrder::Ascending
+    };
+    POOLS
+        .prefix(side)
+        .range(deps.storage, None, None, order)
+        .skip(offset)
+        .take(limit)
+        .map(|item| {
+            let (key, pool) = item?;
+            Ok(pool_response(config, side, key_price(&key)?, pool))
+        })
+        .collect()
+}
+
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps<KujiraQuery>, env: Env, msg: QueryMsg) -> StdResult<Binary> {
+pub fn query(deps: Deps<KujiraQuery>, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    let config = CONFIG.load(deps.storage)?;
     match msg {
+        QueryMsg::Config {} => to_json_binary(&ConfigResponse {
+            owner: config.owner.clone(),
+            denoms: [
+                Denom::Native(config.denoms[0].clone()),
+                Denom::Native(config.denoms[1].clone()),
+            ],
+            price_precision: config.price_precision.clone(),
+            decimal_delta: config.decimal_delta,
+            is_bootstrapping: config.is_bootstrapping,
+            fee_taker: config.fee_taker,
+            fee_maker: config.fee_maker,
+            fee_address: config.fee_address,
+        }),
+        QueryMsg::Simulation { offer_asset } => {
+            let denom = match offer_asset.info {
+                AssetInfo::NativeToken { denom } => denom,
+                AssetInfo::Token { .. } => {
+                    return Err(StdError::generic_err("CW20 offer assets are not supported"))
+                }
+            };
+            let side = config.side(denom.as_ref())?;
+            let exec = simulate(deps.storage, side, offer_asset.amount.into(), None)?;
+            let commission_amount = exec.return_amount.mul_floor(config.fee_taker);
+            to_json_binary(&SimulationResponse {
+                return_amount: exec.return_amount - commission_amount,
+                spread_amount: exec.spread_amount,
+                commission_amount,
+            })
+        }
         QueryMsg::Order { order_idx } => {
             let order = ORDERS.load(deps.storage, order_idx.u128())?;
-            let res = OrderResponse {
-                idx: order_idx,
-                owner: order.owner,
-                quote_price: order.price,
-                offer_denom: Denom::Native(order.denom),
-                offer_amount: order.amount.into(),
-                filled_amount: order.filled.into(),
-                created_at: env.block.time,
-                original_offer_amount: order.amount.into(),
-            };
-            Ok(to_json_binary(&res)?)
+            to_json_binary(&order_response(&config, order_idx.u128(), order))
+        }
+        QueryMsg::OrdersByUser {
+            address,
+            start_after,
+            limit,
+        } => {
+            let limit = limit.unwrap_or(DEFAULT_LIMIT).min(DEFAULT_LIMIT) as usize;
+            let min = start_after.map(|x| Bound::exclusive(x.u128()));
+            let orders = USER_ORDERS
+                .prefix(&address)
+                .keys(deps.storage, min, None, SortOrder::Ascending)
+                .take(limit)
+                .map(|idx| {
+                    let idx = idx?;
+                    let order = ORDERS.load(deps.storage, idx)?;
+                    Ok(order_response(&config, idx, order))
+                })
+                .collect::<StdResult<Vec<OrderResponse>>>()?;
+            to_json_binary(&OrdersResponse { orders })
+        }
+        QueryMsg::Price { price } => {
+            let key = price_key(&price);
+            let base = POOLS
+                .may_load(deps.storage, (BASE, key.clone()))?
+                .unwrap_or_default();
+            let quote = POOLS
+                .may_load(deps.storage, (QUOTE, key))?
+                .unwrap_or_default();
+            to_json_binary(&PriceResponse {
+                pools: [
+                    pool_response(&config, BASE, price, base),
+                    pool_response(&config, QUOTE, price, quote),
+                ],
+            })
+        }
+        QueryMsg::Book { limit, offset } => {
+            let limit = limit.unwrap_or(DEFAULT_LIMIT) as usize;
+            let offset = offset.unwrap_or_default() as usize;
+            to_json_binary(&BookResponse {
+                base: book_side(deps, &config, BASE, limit, offset)?,
+                quote: book_side(deps, &config, QUOTE, limit, offset)?,
+            })
         }
-        _ => Ok(Binary::default()),
     }
 }
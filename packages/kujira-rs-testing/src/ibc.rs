@@ -0,0 +1,110 @@
+//! Fake remote chain state for IBC-verify queries on [KujiraModule](crate::mock::KujiraModule).
+//! Proofs are not checked; a query passes when the seeded state matches it
+
+use anyhow::{Error, Result as AnyResult};
+use cosmwasm_std::{to_json_binary, Binary, Order, Storage};
+use cw_storage_plus::{Bound, Map};
+use kujira::{IbcVerifyQuery, IbcVerifyResponse};
+
+/// (connection, revision_number, path), revision_height
+type StateKey<'a> = ((&'a str, u64, &'a [u8]), u64);
+
+/// Remote values by the height they were written at. `None` records a deletion
+static REMOTE_STATE: Map<StateKey, Option<Binary>> = Map::new("ibc_remote_state");
+
+/// Writes `value` at `path_prefix`/`path_key` on the chain at the end of `connection`,
+/// from `revision_height` onwards. `None` deletes it
+pub fn set_remote_state(
+    storage: &mut dyn Storage,
+    connection: &str,
+    revision_number: u64,
+    revision_height: u64,
+    path_prefix: &str,
+    path_key: &[u8],
+    value: Option<Binary>,
+) -> AnyResult<()> {
+    let path = path(path_prefix, path_key);
+    REMOTE_STATE.save(
+        storage,
+        ((connection, revision_number, &path), revision_height),
+        &value,
+    )?;
+    Ok(())
+}
+
+/// The remote value at `path_prefix`/`path_key` as of `revision_height`
+pub fn remote_state(
+    storage: &dyn Storage,
+    connection: &str,
+    revision_number: u64,
+    revision_height: u64,
+    path_prefix: &str,
+    path_key: &[u8],
+) -> AnyResult<Option<Binary>> {
+    let latest = REMOTE_STATE
+        .prefix((connection, revision_number, &path(path_prefix, path_key)))
+        .range(
+            storage,
+            None,
+            Some(Bound::inclusive(revision_height)),
+            Order::Descending,
+        )
+        .next()
+        .transpose()?;
+    Ok(latest.and_then(|(_, value)| value))
+}
+
+/// Length-prefixes `path_prefix`, so that distinct prefix/key pairs can't collide
+fn path(path_prefix: &str, path_key: &[u8]) -> Vec<u8> {
+    let mut path = (path_prefix.len() as u32).to_be_bytes().to_vec();
+    path.extend_from_slice(path_prefix.as_bytes());
+    path.extend_from_slice(path_key);
+    path
+}
+
+pub(crate) fn query(storage: &dyn Storage, query: IbcVerifyQuery) -> AnyResult<Binary> {
+    match query {
+        IbcVerifyQuery::VerifyMembership {
+            connection,
+            revision_number,
+            revision_height,
+            value,
+            path_prefix,
+            path_key,
+            ..
+        } => {
+            let stored = remote_state(
+                storage,
+                &connection,
+                revision_number,
+                revision_height,
+                &path_prefix,
+                &path_key,
+            )?;
+            if stored != Some(value) {
+                return Err(Error::msg("membership verification failed"));
+            }
+        }
+        IbcVerifyQuery::VerifyNonMembership {
+            connection,
+            revision_number,
+            revision_height,
+            path_prefix,
+            path_key,
+            ..
+        } => {
+            let stored = remote_state(
+                storage,
+                &connection,
+                revision_number,
+                revision_height,
+                &path_prefix,
+                &path_key,
+            )?;
+            if stored.is_some() {
+                return Err(Error::msg("non-membership verification failed"));
+            }
+        }
+    }
+    Ok(to_json_binary(&IbcVerifyResponse {})?)
+}
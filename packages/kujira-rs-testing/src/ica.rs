@@ -0,0 +1,576 @@
+//! A mock ICA host and IBC transfer app for [KujiraModule](crate::mock::KujiraModule).
+//! Accounts are registered at deterministic addresses, and submitted messages and outbound
+//! transfers are recorded until a test resolves them with [KujiraSudo](crate::mock::KujiraSudo)
+
+use anyhow::{Error, Result as AnyResult};
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{
+    attr, to_json_binary, to_json_string, Addr, Api, BankMsg, Binary, BlockInfo, Coin, CosmosMsg,
+    CustomMsg, CustomQuery, Event, IbcTimeout, Order, QueryRequest, Storage, WasmQuery,
+};
+use cw_multi_test::{AppResponse, BankSudo, CosmosRouter, SudoMsg, WasmSudo};
+use cw_storage_plus::Map;
+use kujira::{
+    AccountAddressResponse, IcaMsg, IcaRegisterVersion, IcaSudoMsg, IcaTxCallbackData, IcaTxResult,
+    Ics27MetadataOpen, ProtobufAny, TransferCallbackData, TransferReceiptData,
+};
+use serde::de::DeserializeOwned;
+use sha2::{Digest, Sha256};
+
+use crate::api::MockApiBech32;
+
+/// The address prefix of the simulated host chain
+pub const ICA_HOST_PREFIX: &str = "cosmos";
+
+#[cw_serde]
+pub enum IcaAccountStatus {
+    /// Registration requested, awaiting [KujiraSudo::IcaRegister](crate::mock::KujiraSudo::IcaRegister)
+    Pending,
+    Open,
+    /// The channel was closed by an error or timeout, and must be registered again
+    Closed,
+}
+
+#[cw_serde]
+pub struct IcaAccount {
+    pub address: String,
+    pub status: IcaAccountStatus,
+    /// Registered with an ICS-29 version. `IcaRegisterVersion` can't be stored, as it
+    /// doesn't deserialize from its own serialization
+    pub fee_enabled: bool,
+    pub callback: Option<Binary>,
+    pub next_sequence: u64,
+}
+
+#[cw_serde]
+pub struct IcaTx {
+    pub msgs: Vec<ProtobufAny>,
+    pub memo: String,
+    pub timeout: u64,
+    pub callback: Option<Binary>,
+    /// Set once the tx has been resolved by a test
+    pub result: Option<IcaTxResult>,
+}
+
+#[cw_serde]
+pub struct IbcTransfer {
+    pub sender: Addr,
+    pub receiver: String,
+    pub amount: Coin,
+    pub timeout: IbcTimeout,
+    pub callback: Binary,
+    /// Set once the packet has been resolved by a test
+    pub result: Option<IcaTxResult>,
+}
+
+/// The outcome of a registration, delivered to the controller as an `IcaRegisterResult`
+#[cw_serde]
+pub enum IcaRegisterOutcome {
+    Success {},
+    Error { error: String },
+    Timeout {},
+}
+
+type AccountKey<'a> = (&'a Addr, &'a str, &'a str);
+
+static ICA_ACCOUNTS: Map<AccountKey, IcaAccount> = Map::new("ica_accounts");
+static ICA_TXS: Map<(AccountKey, u64), IcaTx> = Map::new("ica_txs");
+static TRANSFERS: Map<(&str, u64), IbcTransfer> = Map::new("ibc_transfers");
+static TRANSFER_SEQUENCE: Map<&str, u64> = Map::new("ibc_transfer_sequence");
+static RECEIPT_SEQUENCE: Map<&str, u64> = Map::new("ibc_receipt_sequence");
+
+const TRANSFER_PORT: &str = "transfer";
+
+/// The wire format of `IcaSudoMsg::IcaRegisterCallback`. `IcaOpenVersion` is
+/// received as base64-encoded JSON, which its `Serialize` impl doesn't produce
+#[cw_serde]
+enum RegisterCallbackMsg {
+    IcaRegisterCallback(RegisterCallbackData),
+}
+
+#[cw_serde]
+struct RegisterCallbackData {
+    connection_id: String,
+    account_id: String,
+    callback: Option<Binary>,
+    result: RegisterCallbackResult,
+}
+
+#[cw_serde]
+enum RegisterCallbackResult {
+    Success { data: Binary },
+    Error { error: String },
+    Timeout {},
+}
+
+#[cw_serde]
+struct Ics29OpenData {
+    fee_version: String,
+    app_version: String,
+}
+
+/// The address an interchain account is opened at
+pub fn account_address(owner: &Addr, connection_id: &str, account_id: &str) -> String {
+    let seed = format!("ica/{owner}/{connection_id}/{account_id}");
+    let digest = Sha256::digest(seed.as_bytes());
+    MockApiBech32::new(ICA_HOST_PREFIX)
+        .addr_make(&hex::encode(digest))
+        .to_string()
+}
+
+/// The address holding funds sent over `channel_id` until their packet is resolved
+pub fn escrow_address(channel_id: &str) -> Addr {
+    MockApiBech32::new("kujira").addr_make(&format!("{TRANSFER_PORT}/{channel_id}"))
+}
+
+/// Loads an outbound transfer by its packet sequence
+pub fn transfer(
+    storage: &dyn Storage,
+    channel_id: &str,
+    sequence: u64,
+) -> AnyResult<Option<IbcTransfer>> {
+    Ok(TRANSFERS.may_load(storage, (channel_id, sequence))?)
+}
+
+/// All outbound transfers over a channel, by packet sequence
+pub fn transfers(storage: &dyn Storage, channel_id: &str) -> AnyResult<Vec<(u64, IbcTransfer)>> {
+    Ok(TRANSFERS
+        .prefix(channel_id)
+        .range(storage, None, None, Order::Ascending)
+        .collect::<Result<Vec<_>, _>>()?)
+}
+
+/// Loads an account registered by `owner`
+pub fn account(
+    storage: &dyn Storage,
+    owner: &Addr,
+    connection_id: &str,
+    account_id: &str,
+) -> AnyResult<Option<IcaAccount>> {
+    Ok(ICA_ACCOUNTS.may_load(storage, (owner, connection_id, account_id))?)
+}
+
+/// All txs submitted through an account, by sequence
+pub fn txs(
+    storage: &dyn Storage,
+    owner: &Addr,
+    connection_id: &str,
+    account_id: &str,
+) -> AnyResult<Vec<(u64, IcaTx)>> {
+    Ok(ICA_TXS
+        .prefix((owner, connection_id, account_id))
+        .range(storage, None, None, Order::Ascending)
+        .collect::<Result<Vec<_>, _>>()?)
+}
+
+pub(crate) fn execute<ExecC, QueryC>(
+    api: &dyn Api,
+    storage: &mut dyn Storage,
+    router: &dyn CosmosRouter<ExecC = ExecC, QueryC = QueryC>,
+    block: &BlockInfo,
+    sender: Addr,
+    msg: IcaMsg,
+) -> AnyResult<AppResponse>
+where
+    ExecC: CustomMsg + DeserializeOwned + 'static,
+    QueryC: CustomQuery + DeserializeOwned + 'static,
+{
+    match msg {
+        IcaMsg::Register {
+            connection_id,
+            account_id,
+            version,
+            callback,
+        } => {
+            let key = (&sender, connection_id.as_str(), account_id.as_str());
+            let next_sequence = match ICA_ACCOUNTS.may_load(storage, key)? {
+                Some(IcaAccount {
+                    status: IcaAccountStatus::Closed,
+                    next_sequence,
+                    ..
+                }) => next_sequence,
+                Some(_) => return Err(Error::msg("ICA account already registered")),
+                None => 1,
+            };
+            let address = account_address(&sender, &connection_id, &account_id);
+            ICA_ACCOUNTS.save(
+                storage,
+                key,
+                &IcaAccount {
+                    address,
+                    status: IcaAccountStatus::Pending,
+                    fee_enabled: matches!(version, IcaRegisterVersion::Ics29(_)),
+                    callback,
+                    next_sequence,
+                },
+            )?;
+
+            Ok(AppResponse {
+                events: vec![Event::new("ica_register").add_attributes(vec![
+                    attr("owner", sender.to_string()),
+                    attr("connection_id", connection_id),
+                    attr("account_id", account_id),
+                ])],
+                data: None,
+            })
+        }
+        IcaMsg::Submit {
+            connection_id,
+            account_id,
+            msgs,
+            memo,
+            timeout,
+            callback,
+        } => {
+            let key = (&sender, connection_id.as_str(), account_id.as_str());
+            let mut account = ICA_ACCOUNTS
+                .may_load(storage, key)?
+                .filter(|a| a.status == IcaAccountStatus::Open)
+                .ok_or_else(|| Error::msg("ICA account not open"))?;
+            let sequence = account.next_sequence;
+            account.next_sequence += 1;
+            ICA_ACCOUNTS.save(storage, key, &account)?;
+            ICA_TXS.save(
+                storage,
+                (key, sequence),
+                &IcaTx {
+                    msgs,
+                    memo,
+                    timeout,
+                    callback,
+                    result: None,
+                },
+            )?;
+
+            Ok(AppResponse {
+                events: vec![Event::new("ica_submit").add_attributes(vec![
+                    attr("owner", sender.to_string()),
+                    attr("connection_id", connection_id),
+                    attr("account_id", account_id),
+                    attr("sequence", sequence.to_string()),
+                ])],
+                data: None,
+            })
+        }
+        IcaMsg::Transfer {
+            channel_id,
+            to_address,
+            amount,
+            timeout,
+            callback,
+        } => {
+            router.execute(
+                api,
+                storage,
+                block,
+                sender.clone(),
+                CosmosMsg::Bank(BankMsg::Send {
+                    to_address: escrow_address(&channel_id).to_string(),
+                    amount: vec![amount.clone()],
+                }),
+            )?;
+            let sequence = TRANSFER_SEQUENCE
+                .may_load(storage, &channel_id)?
+                .unwrap_or_default()
+                + 1;
+            TRANSFER_SEQUENCE.save(storage, &channel_id, &sequence)?;
+            TRANSFERS.save(
+                storage,
+                (&channel_id, sequence),
+                &IbcTransfer {
+                    sender: sender.clone(),
+                    receiver: to_address.clone(),
+                    amount: amount.clone(),
+                    timeout,
+                    callback,
+                    result: None,
+                },
+            )?;
+
+            Ok(AppResponse {
+                events: vec![Event::new("ibc_transfer").add_attributes(vec![
+                    attr("sender", sender.to_string()),
+                    attr("receiver", to_address),
+                    attr("amount", amount.to_string()),
+                    attr("channel_id", channel_id),
+                    attr("sequence", sequence.to_string()),
+                ])],
+                data: None,
+            })
+        }
+    }
+}
+
+pub(crate) fn query_address(
+    storage: &dyn Storage,
+    owner: &Addr,
+    connection_id: &str,
+    account_id: &str,
+) -> AnyResult<Binary> {
+    match account(storage, owner, connection_id, account_id)? {
+        Some(IcaAccount {
+            address,
+            status: IcaAccountStatus::Open,
+            ..
+        }) => Ok(to_json_binary(&AccountAddressResponse { address })?),
+        _ => Err(Error::msg("ICA account not found")),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn resolve_register<ExecC, QueryC>(
+    api: &dyn cosmwasm_std::Api,
+    storage: &mut dyn Storage,
+    router: &dyn CosmosRouter<ExecC = ExecC, QueryC = QueryC>,
+    block: &BlockInfo,
+    owner: Addr,
+    connection_id: String,
+    account_id: String,
+    outcome: IcaRegisterOutcome,
+) -> AnyResult<AppResponse>
+where
+    ExecC: CustomMsg + DeserializeOwned + 'static,
+    QueryC: CustomQuery + DeserializeOwned + 'static,
+{
+    let key = (&owner, connection_id.as_str(), account_id.as_str());
+    let mut account = ICA_ACCOUNTS
+        .may_load(storage, key)?
+        .filter(|a| a.status == IcaAccountStatus::Pending)
+        .ok_or_else(|| Error::msg("No pending ICA registration"))?;
+
+    let result = match outcome {
+        IcaRegisterOutcome::Success {} => {
+            account.status = IcaAccountStatus::Open;
+            RegisterCallbackResult::Success {
+                data: open_version(&account, &connection_id)?,
+            }
+        }
+        IcaRegisterOutcome::Error { error } => {
+            account.status = IcaAccountStatus::Closed;
+            RegisterCallbackResult::Error { error }
+        }
+        IcaRegisterOutcome::Timeout {} => {
+            account.status = IcaAccountStatus::Closed;
+            RegisterCallbackResult::Timeout {}
+        }
+    };
+    ICA_ACCOUNTS.save(storage, key, &account)?;
+
+    let msg = RegisterCallbackMsg::IcaRegisterCallback(RegisterCallbackData {
+        connection_id,
+        account_id,
+        callback: account.callback,
+        result,
+    });
+    router.sudo(
+        api,
+        storage,
+        block,
+        SudoMsg::Wasm(WasmSudo {
+            contract_addr: owner,
+            message: to_json_binary(&msg)?,
+        }),
+    )
+}
+
+/// Resolves a submitted tx. On an ordered channel, a timeout closes the account
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn resolve_tx<ExecC, QueryC>(
+    api: &dyn cosmwasm_std::Api,
+    storage: &mut dyn Storage,
+    router: &dyn CosmosRouter<ExecC = ExecC, QueryC = QueryC>,
+    block: &BlockInfo,
+    owner: Addr,
+    connection_id: String,
+    account_id: String,
+    sequence: u64,
+    result: IcaTxResult,
+) -> AnyResult<AppResponse>
+where
+    ExecC: CustomMsg + DeserializeOwned + 'static,
+    QueryC: CustomQuery + DeserializeOwned + 'static,
+{
+    let key = (&owner, connection_id.as_str(), account_id.as_str());
+    let mut tx = ICA_TXS
+        .may_load(storage, (key, sequence))?
+        .filter(|tx| tx.result.is_none())
+        .ok_or_else(|| Error::msg("No pending ICA tx"))?;
+    tx.result = Some(result.clone());
+    ICA_TXS.save(storage, (key, sequence), &tx)?;
+
+    if let IcaTxResult::Timeout {} = result {
+        let mut account = ICA_ACCOUNTS.load(storage, key)?;
+        account.status = IcaAccountStatus::Closed;
+        ICA_ACCOUNTS.save(storage, key, &account)?;
+    }
+
+    let msg = IcaSudoMsg::IcaTxCallback(IcaTxCallbackData {
+        connection_id,
+        account_id,
+        sequence,
+        callback: tx.callback,
+        result,
+    });
+    router.sudo(
+        api,
+        storage,
+        block,
+        SudoMsg::Wasm(WasmSudo {
+            contract_addr: owner,
+            message: to_json_binary(&msg)?,
+        }),
+    )
+}
+
+fn open_version(account: &IcaAccount, connection_id: &str) -> AnyResult<Binary> {
+    let metadata = Ics27MetadataOpen {
+        version: "ics27-1".to_string(),
+        controller_connection_id: connection_id.to_string(),
+        host_connection_id: connection_id.to_string(),
+        address: account.address.clone(),
+        encoding: "proto3".to_string(),
+        tx_type: "sdk_multi_msg".to_string(),
+    };
+    if account.fee_enabled {
+        Ok(to_json_binary(&Ics29OpenData {
+            fee_version: "ics29-1".to_string(),
+            app_version: to_json_string(&metadata)?,
+        })?)
+    } else {
+        Ok(to_json_binary(&metadata)?)
+    }
+}
+
+/// Resolves an outbound transfer. Funds stay in escrow on success, and are refunded
+/// to the sender on an error or timeout
+pub(crate) fn resolve_transfer<ExecC, QueryC>(
+    api: &dyn Api,
+    storage: &mut dyn Storage,
+    router: &dyn CosmosRouter<ExecC = ExecC, QueryC = QueryC>,
+    block: &BlockInfo,
+    channel_id: String,
+    sequence: u64,
+    result: IcaTxResult,
+) -> AnyResult<AppResponse>
+where
+    ExecC: CustomMsg + DeserializeOwned + 'static,
+    QueryC: CustomQuery + DeserializeOwned + 'static,
+{
+    let mut transfer = TRANSFERS
+        .may_load(storage, (&channel_id, sequence))?
+        .filter(|t| t.result.is_none())
+        .ok_or_else(|| Error::msg("No pending IBC transfer"))?;
+    transfer.result = Some(result.clone());
+    TRANSFERS.save(storage, (&channel_id, sequence), &transfer)?;
+
+    let mut events = vec![];
+    if !matches!(result, IcaTxResult::Success { .. }) {
+        let res = router.execute(
+            api,
+            storage,
+            block,
+            escrow_address(&channel_id),
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: transfer.sender.to_string(),
+                amount: vec![transfer.amount.clone()],
+            }),
+        )?;
+        events.extend(res.events);
+    }
+
+    let msg = IcaSudoMsg::TransferCallback(TransferCallbackData {
+        port: TRANSFER_PORT.to_string(),
+        channel: channel_id,
+        sequence,
+        receiver: transfer.receiver,
+        denom: transfer.amount.denom,
+        amount: transfer.amount.amount.to_string(),
+        memo: String::default(),
+        result,
+        callback: transfer.callback,
+    });
+    let res = router.sudo(
+        api,
+        storage,
+        block,
+        SudoMsg::Wasm(WasmSudo {
+            contract_addr: transfer.sender,
+            message: to_json_binary(&msg)?,
+        }),
+    )?;
+    events.extend(res.events);
+
+    Ok(AppResponse {
+        events,
+        data: res.data,
+    })
+}
+
+/// Credits an inbound transfer to `receiver`, minting `amount` as if it had been
+/// received over `channel_id`. Contract receivers are sent `IcaSudoMsg::TransferReceipt`
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn receive_transfer<ExecC, QueryC>(
+    api: &dyn Api,
+    storage: &mut dyn Storage,
+    router: &dyn CosmosRouter<ExecC = ExecC, QueryC = QueryC>,
+    block: &BlockInfo,
+    channel_id: String,
+    sender: String,
+    receiver: Addr,
+    amount: Coin,
+    memo: String,
+) -> AnyResult<AppResponse>
+where
+    ExecC: CustomMsg + DeserializeOwned + 'static,
+    QueryC: CustomQuery + DeserializeOwned + 'static,
+{
+    let sequence = RECEIPT_SEQUENCE
+        .may_load(storage, &channel_id)?
+        .unwrap_or_default()
+        + 1;
+    RECEIPT_SEQUENCE.save(storage, &channel_id, &sequence)?;
+
+    let mut res = router.sudo(
+        api,
+        storage,
+        block,
+        SudoMsg::Bank(BankSudo::Mint {
+            to_address: receiver.to_string(),
+            amount: vec![amount.clone()],
+        }),
+    )?;
+
+    let is_contract = router
+        .query(
+            api,
+            storage,
+            block,
+            QueryRequest::Wasm(WasmQuery::ContractInfo {
+                contract_addr: receiver.to_string(),
+            }),
+        )
+        .is_ok();
+    if is_contract {
+        let msg = IcaSudoMsg::TransferReceipt(TransferReceiptData {
+            port: TRANSFER_PORT.to_string(),
+            channel: channel_id,
+            sequence,
+            sender,
+            denom: amount.denom,
+            amount: amount.amount.to_string(),
+            memo,
+        });
+        let receipt = router.sudo(
+            api,
+            storage,
+            block,
+            SudoMsg::Wasm(WasmSudo {
+                contract_addr: receiver,
+                message: to_json_binary(&msg)?,
+            }),
+        )?;
+        res.events.extend(receipt.events);
+        res.data = receipt.data;
+    }
+    Ok(res)
+}
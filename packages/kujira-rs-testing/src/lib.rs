@@ -1,9 +1,12 @@
 pub mod address;
 pub mod api;
 pub mod fin;
+pub mod ibc;
+pub mod ica;
 pub mod mock;
 pub mod orca;
 pub mod state;
+pub mod vesting;
 
 pub mod util;
 pub use util::*;
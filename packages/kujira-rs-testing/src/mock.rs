@@ -1,9 +1,11 @@
 use std::{collections::HashMap, convert::TryInto};
 
 use anyhow::{Error, Result as AnyResult};
+use cosmwasm_schema::cw_serde;
 use cosmwasm_std::{
-    attr, testing::MockStorage, to_json_binary, Addr, BankMsg, Coin, CosmosMsg, Decimal, Empty,
-    Event, Uint128,
+    attr, from_json, testing::MockStorage, to_json_binary, Addr, AllDelegationsResponse, BankMsg,
+    Binary, Coin, CosmosMsg, Decimal, DistributionMsg, Event, Order, QueryRequest, StakingQuery,
+    Uint128,
 };
 
 use cw_multi_test::{
@@ -13,14 +15,20 @@ use cw_multi_test::{
 
 use cw_storage_plus::Map;
 use kujira::{
-    BankQuery, DenomAdminResponse, DenomMsg, DenomQuery, ExchangeRateResponse, FullDenomResponse,
-    KujiraMsg, KujiraQuery, OracleQuery, SupplyResponse,
+    AuthMsg, BankQuery, BatchMsg, DenomAdminResponse, DenomMsg, DenomQuery, ExchangeRateResponse,
+    FullDenomResponse, IcaQuery, IcaTxResult, KujiraMsg, KujiraQuery, OracleQuery, SupplyResponse,
 };
 
-use crate::{address::MockAddressGenerator, api::MockApiBech32};
+use crate::{
+    address::MockAddressGenerator,
+    api::MockApiBech32,
+    ibc,
+    ica::{self, IcaRegisterOutcome},
+    vesting::{self, VestingBankKeeper},
+};
 
 pub type CustomApp = App<
-    BankKeeper,
+    VestingBankKeeper,
     MockApiBech32,
     MockStorage,
     KujiraModule,
@@ -38,6 +46,7 @@ pub fn mock_app(balances: Vec<(Addr, Vec<Coin>)>) -> CustomApp {
 
     BasicAppBuilder::new_custom()
         .with_custom(custom)
+        .with_bank(VestingBankKeeper::new())
         .with_api(MockApiBech32::new("kujira"))
         .with_wasm(WasmKeeper::default().with_address_generator(MockAddressGenerator))
         .build(|router, _, storage| {
@@ -47,10 +56,89 @@ pub fn mock_app(balances: Vec<(Addr, Vec<Coin>)>) -> CustomApp {
         })
 }
 
+/// Runs a [KujiraSudo] message on the app's [KujiraModule].
+/// `App::sudo` can only route `SudoMsg::Custom(Empty)`, so this is the entry point
+/// for changing chain state between blocks.
+///
+/// `init_modules` writes straight to the app's storage, so state is restored by hand
+/// if the message fails, e.g. when a contract rejects its ICA or transfer callback
+pub fn sudo(app: &mut CustomApp, msg: KujiraSudo) -> AnyResult<AppResponse> {
+    let block = app.block_info();
+    app.init_modules(|router, api, storage| {
+        let snapshot: Vec<_> = storage.range(None, None, Order::Ascending).collect();
+        let res = router.custom.sudo(api, storage, router, &block, msg);
+        if res.is_err() {
+            let keys: Vec<_> = storage.range_keys(None, None, Order::Ascending).collect();
+            for key in keys {
+                storage.remove(&key);
+            }
+            for (key, value) in snapshot {
+                storage.set(&key, &value);
+            }
+        }
+        res
+    })
+}
+
 pub struct KujiraModule {
+    /// Prices set before the app is built. Prices set with [KujiraSudo] take precedence
     pub oracle_prices: HashMap<String, Decimal>,
 }
 
+/// Privileged messages to update the mock chain state mid-test
+#[cw_serde]
+pub enum KujiraSudo {
+    /// Sets the oracle exchange rate for a denom
+    SetOraclePrice { denom: String, price: Decimal },
+    /// Removes the oracle exchange rate for a denom. Queries for it will return zero
+    RemoveOraclePrice { denom: String },
+    /// Sets the oracle exchange rate for many denoms at once
+    SetOraclePrices { prices: Vec<(String, Decimal)> },
+    /// Completes a pending `IcaMsg::Register`, sending `IcaSudoMsg::IcaRegisterCallback` to the owner.
+    /// On success the account is opened at [ica::account_address]
+    IcaRegister {
+        owner: Addr,
+        connection_id: String,
+        account_id: String,
+        result: IcaRegisterOutcome,
+    },
+    /// Completes a submitted `IcaMsg::Submit` by its sequence, sending `IcaSudoMsg::IcaTxCallback` to the owner
+    IcaTx {
+        owner: Addr,
+        connection_id: String,
+        account_id: String,
+        sequence: u64,
+        result: IcaTxResult,
+    },
+    /// Acknowledges, fails or times out an outbound `IcaMsg::Transfer` by its packet sequence,
+    /// sending `IcaSudoMsg::TransferCallback` to the sender. Escrowed funds are refunded
+    /// unless the result is a success
+    IbcTransfer {
+        channel_id: String,
+        sequence: u64,
+        result: IcaTxResult,
+    },
+    /// Delivers an inbound transfer over `channel_id`, minting `amount` to `receiver`.
+    /// Contracts are sent `IcaSudoMsg::TransferReceipt`
+    IbcTransferReceipt {
+        channel_id: String,
+        sender: String,
+        receiver: Addr,
+        amount: Coin,
+        memo: String,
+    },
+    /// Writes a value to the remote chain at the end of `connection`, as of `revision_height`.
+    /// `None` deletes it. IBC-verify queries check membership against this state
+    SetRemoteState {
+        connection: String,
+        revision_number: u64,
+        revision_height: u64,
+        path_prefix: String,
+        path_key: Binary,
+        value: Option<Binary>,
+    },
+}
+
 impl KujiraModule {
     pub fn set_oracle_price(&mut self, price: Decimal, denom: &str) {
         self.oracle_prices.insert(denom.to_string(), price);
@@ -59,16 +147,24 @@ impl KujiraModule {
     fn subdenom_to_full(sender: impl Into<String>, subdenom: impl Into<String>) -> String {
         format!("factory/{}/{}", sender.into(), subdenom.into())
     }
+
+    fn oracle_price(&self, storage: &dyn cosmwasm_std::Storage, denom: &str) -> AnyResult<Decimal> {
+        match ORACLE_PRICES.may_load(storage, denom)? {
+            Some(price) => Ok(price),
+            None => Ok(*self.oracle_prices.get(denom).unwrap_or(&Decimal::zero())),
+        }
+    }
 }
 
 static DENOM_ADMINS: Map<String, Addr> = Map::new("denom_admins");
+static ORACLE_PRICES: Map<&str, Decimal> = Map::new("oracle_prices");
 
 impl Module for KujiraModule {
     type ExecT = KujiraMsg;
 
     type QueryT = KujiraQuery;
 
-    type SudoT = Empty;
+    type SudoT = KujiraSudo;
 
     fn execute<ExecC, QueryC>(
         &self,
@@ -90,7 +186,39 @@ impl Module for KujiraModule {
         QueryC: cosmwasm_std::CustomQuery + serde::de::DeserializeOwned + 'static,
     {
         match msg {
-            KujiraMsg::Auth(_) | KujiraMsg::Batch(_) | KujiraMsg::Ica(_) => todo!(),
+            KujiraMsg::Auth(AuthMsg::CreateVestingAccount {
+                to_address,
+                amount,
+                end_time,
+                delayed,
+            }) => vesting::create_vesting_account(
+                api, storage, router, block, sender, to_address, amount, end_time, delayed,
+            ),
+            KujiraMsg::Batch(BatchMsg::WithdrawAllDelegatorRewards {}) => {
+                let delegations: AllDelegationsResponse = from_json(router.query(
+                    api,
+                    storage,
+                    block,
+                    QueryRequest::Staking(StakingQuery::AllDelegations {
+                        delegator: sender.to_string(),
+                    }),
+                )?)?;
+                let mut res = AppResponse::default();
+                for delegation in delegations.delegations {
+                    let withdrawn = router.execute(
+                        api,
+                        storage,
+                        block,
+                        sender.clone(),
+                        CosmosMsg::Distribution(DistributionMsg::WithdrawDelegatorReward {
+                            validator: delegation.validator,
+                        }),
+                    )?;
+                    res.events.extend(withdrawn.events);
+                }
+                Ok(res)
+            }
+            KujiraMsg::Ica(msg) => ica::execute(api, storage, router, block, sender, msg),
             KujiraMsg::Denom(d) => match d {
                 DenomMsg::Create { subdenom } => {
                     let full = Self::subdenom_to_full(sender.clone(), subdenom.to_string());
@@ -177,17 +305,64 @@ impl Module for KujiraModule {
                         data: None,
                     })
                 }
+                DenomMsg::SetMetadata { denom, metadata } => {
+                    let admin = DENOM_ADMINS.load(storage, denom.to_string())?;
+                    if admin != sender {
+                        return Err(Error::msg("Unauthorized"));
+                    }
+                    if metadata.base != denom.as_ref() {
+                        return Err(Error::msg("Metadata base must match denom"));
+                    }
+                    // The bank keeper reads metadata from unprefixed storage,
+                    // so this is visible to BankQuery::DenomMetadata
+                    BankKeeper::new().set_denom_metadata(storage, denom.to_string(), metadata)?;
+
+                    Ok(AppResponse {
+                        events: vec![Event::new("set_denom_metadata")
+                            .add_attribute("denom", denom.to_string())],
+                        data: None,
+                    })
+                }
+                DenomMsg::ForceTransfer {
+                    denom,
+                    amount,
+                    from_address,
+                    to_address,
+                } => {
+                    let admin = DENOM_ADMINS.load(storage, denom.to_string())?;
+                    if admin != sender {
+                        return Err(Error::msg("Unauthorized"));
+                    }
+                    let mut res = router.execute(
+                        api,
+                        storage,
+                        block,
+                        from_address.clone(),
+                        CosmosMsg::Bank(BankMsg::Send {
+                            to_address: to_address.to_string(),
+                            amount: denom.coins(&amount),
+                        }),
+                    )?;
+                    res.events
+                        .push(Event::new("force_transfer").add_attributes(vec![
+                            attr("amount", amount),
+                            attr("denom", denom.to_string()),
+                            attr("from_address", from_address),
+                            attr("to_address", to_address),
+                        ]));
+                    Ok(res)
+                }
             },
         }
     }
 
     fn sudo<ExecC, QueryC>(
         &self,
-        _api: &dyn cosmwasm_std::Api,
-        _storage: &mut dyn cosmwasm_std::Storage,
-        _router: &dyn CosmosRouter<ExecC = ExecC, QueryC = QueryC>,
-        _block: &cosmwasm_std::BlockInfo,
-        _msg: Self::SudoT,
+        api: &dyn cosmwasm_std::Api,
+        storage: &mut dyn cosmwasm_std::Storage,
+        router: &dyn CosmosRouter<ExecC = ExecC, QueryC = QueryC>,
+        block: &cosmwasm_std::BlockInfo,
+        msg: Self::SudoT,
     ) -> AnyResult<AppResponse>
     where
         ExecC: std::fmt::Debug
@@ -195,10 +370,99 @@ impl Module for KujiraModule {
             + PartialEq
             + schemars::JsonSchema
             + serde::de::DeserializeOwned
+            + cosmwasm_std::CustomMsg
             + 'static,
         QueryC: cosmwasm_std::CustomQuery + serde::de::DeserializeOwned + 'static,
     {
-        todo!()
+        let prices = match msg {
+            KujiraSudo::SetOraclePrice { denom, price } => vec![(denom, price)],
+            KujiraSudo::RemoveOraclePrice { denom } => vec![(denom, Decimal::zero())],
+            KujiraSudo::SetOraclePrices { prices } => prices,
+            KujiraSudo::IcaRegister {
+                owner,
+                connection_id,
+                account_id,
+                result,
+            } => {
+                return ica::resolve_register(
+                    api,
+                    storage,
+                    router,
+                    block,
+                    owner,
+                    connection_id,
+                    account_id,
+                    result,
+                )
+            }
+            KujiraSudo::IcaTx {
+                owner,
+                connection_id,
+                account_id,
+                sequence,
+                result,
+            } => {
+                return ica::resolve_tx(
+                    api,
+                    storage,
+                    router,
+                    block,
+                    owner,
+                    connection_id,
+                    account_id,
+                    sequence,
+                    result,
+                )
+            }
+            KujiraSudo::IbcTransfer {
+                channel_id,
+                sequence,
+                result,
+            } => {
+                return ica::resolve_transfer(
+                    api, storage, router, block, channel_id, sequence, result,
+                )
+            }
+            KujiraSudo::IbcTransferReceipt {
+                channel_id,
+                sender,
+                receiver,
+                amount,
+                memo,
+            } => {
+                return ica::receive_transfer(
+                    api, storage, router, block, channel_id, sender, receiver, amount, memo,
+                )
+            }
+            KujiraSudo::SetRemoteState {
+                connection,
+                revision_number,
+                revision_height,
+                path_prefix,
+                path_key,
+                value,
+            } => {
+                ibc::set_remote_state(
+                    storage,
+                    &connection,
+                    revision_number,
+                    revision_height,
+                    &path_prefix,
+                    &path_key,
+                    value,
+                )?;
+                return Ok(AppResponse::default());
+            }
+        };
+        let mut events = vec![];
+        for (denom, price) in prices {
+            ORACLE_PRICES.save(storage, &denom, &price)?;
+            events.push(
+                Event::new("oracle_price")
+                    .add_attributes(vec![attr("denom", denom), attr("price", price.to_string())]),
+            );
+        }
+        Ok(AppResponse { events, data: None })
     }
 
     fn query(
@@ -224,7 +488,7 @@ impl Module for KujiraModule {
             },
             KujiraQuery::Oracle(o) => match o {
                 OracleQuery::ExchangeRate { denom } => Ok(to_json_binary(&ExchangeRateResponse {
-                    rate: *self.oracle_prices.get(&denom).unwrap_or(&Decimal::zero()),
+                    rate: self.oracle_price(storage, &denom)?,
                 })?),
             },
             KujiraQuery::Denom(msg) => match msg {
@@ -232,14 +496,22 @@ impl Module for KujiraModule {
                     creator_addr,
                     subdenom,
                 } => Ok(to_json_binary(&FullDenomResponse {
-                    denom: format!("factory/{}/{}", creator_addr, subdenom).into(),
-                })?),
-                DenomQuery::DenomAdmin { subdenom } => Ok(to_json_binary(&DenomAdminResponse {
-                    admin: DENOM_ADMINS.load(storage, subdenom.to_string())?,
+                    denom: Self::subdenom_to_full(creator_addr, subdenom).into(),
                 })?),
+                // Despite the field name, the chain expects the full denom here
+                DenomQuery::DenomAdmin { subdenom: denom } => {
+                    let admin = DENOM_ADMINS
+                        .may_load(storage, denom.clone())?
+                        .ok_or_else(|| Error::msg(format!("denom {denom} has no admin")))?;
+                    Ok(to_json_binary(&DenomAdminResponse { admin })?)
+                }
             },
-            KujiraQuery::Ica(_) => todo!("ICA queries not implemented in mock"),
-            KujiraQuery::Ibc(_) => todo!("IBC-Verify queries not implemented in mock"),
+            KujiraQuery::Ica(IcaQuery::AccountAddress {
+                owner,
+                connection_id,
+                account_id,
+            }) => ica::query_address(storage, &owner, &connection_id, &account_id),
+            KujiraQuery::Ibc(q) => ibc::query(storage, q),
         }
     }
 }
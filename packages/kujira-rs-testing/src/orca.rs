@@ -7,15 +7,24 @@ use cosmwasm_std::{
 };
 use cw_storage_plus::Item;
 use kujira::{
-    amount, fee_address,
     orca::{ExecuteMsg, InstantiateMsg, QueryMsg, SimulationResponse},
-    Denom, KujiraMsg, KujiraQuery,
+    Funds, KujiraMsg, KujiraQuery,
 };
 
-const COLLATERAL: &str = "factory/owner/coll";
-
-const LIQUIDATION_FEE: Item<Decimal> = Item::new("liquidation_fee");
-const REPAY_DENOM: Item<Denom> = Item::new("repay_denom");
+const CONFIG: Item<InstantiateMsg> = Item::new("config");
+
+/// The share of the collateral value repaid to the market. Liquidations are
+/// filled as though all bids sit in the highest premium slot. Slots are
+/// numbered from zero, so the highest is `max_slot - 1`
+fn net_premium(config: &InstantiateMsg) -> StdResult<Decimal> {
+    let premium = config
+        .premium_rate_per_slot
+        .checked_mul(Decimal::from_ratio(
+            config.max_slot.saturating_sub(1),
+            1u128,
+        ))?;
+    Ok(Decimal::one().checked_sub(premium)?)
+}
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
@@ -24,8 +33,7 @@ pub fn instantiate(
     _info: MessageInfo,
     msg: InstantiateMsg,
 ) -> StdResult<Response<KujiraMsg>> {
-    LIQUIDATION_FEE.save(deps.storage, &msg.liquidation_fee)?;
-    REPAY_DENOM.save(deps.storage, &msg.bid_denom)?;
+    CONFIG.save(deps.storage, &msg)?;
     Ok(Response::default())
 }
 
@@ -43,18 +51,18 @@ pub fn execute(
             callback,
             ..
         } => {
-            let collateral_amount = amount(&COLLATERAL.into(), info.funds)?;
+            let config = CONFIG.load(deps.storage)?;
+            let collateral_amount = Funds::new(info.funds)?.exactly(&config.collateral_denom)?;
 
-            let net_premium = Decimal::from_ratio(95u128, 100u128);
-            let repay_amount = collateral_amount.mul_floor(exchange_rate * net_premium);
-            let fee_amount = repay_amount.mul_floor(LIQUIDATION_FEE.load(deps.storage)?);
+            let repay_amount = collateral_amount.mul_floor(exchange_rate * net_premium(&config)?);
+            let fee_amount = repay_amount.mul_floor(config.liquidation_fee);
             let repay_amount = repay_amount - fee_amount;
-            let repay_denom = REPAY_DENOM.load(deps.storage)?;
+            let repay_denom = config.bid_denom;
 
             let mut msgs = vec![];
             if fee_amount.gt(&Uint128::zero()) {
                 msgs.push(CosmosMsg::Bank(BankMsg::Send {
-                    to_address: fee_address().to_string(),
+                    to_address: config.fee_address.to_string(),
                     amount: coins(fee_amount.u128(), repay_denom.to_string()),
                 }));
             }
@@ -86,15 +94,16 @@ pub fn execute(
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn query(deps: Deps<KujiraQuery>, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    let config = CONFIG.load(deps.storage)?;
+    let net_premium = net_premium(&config)?;
     match msg {
         QueryMsg::Simulate {
             collateral_amount,
             exchange_rate,
             ..
         } => {
-            let net_premium = Decimal::from_ratio(95u128, 100u128);
             let repay_amount = collateral_amount.mul_floor(exchange_rate * net_premium);
-            let fee_amount = repay_amount.mul_floor(LIQUIDATION_FEE.load(deps.storage)?);
+            let fee_amount = repay_amount.mul_floor(config.liquidation_fee);
             let repay_amount = repay_amount - fee_amount;
             let res = SimulationResponse {
                 collateral_amount,
@@ -109,13 +118,20 @@ pub fn query(deps: Deps<KujiraQuery>, _env: Env, msg: QueryMsg) -> StdResult<Bin
             exchange_rate,
             ..
         } => {
+            if net_premium.is_zero() {
+                return Err(StdError::generic_err(
+                    "Cannot simulate reverse with a 100% premium",
+                ));
+            }
             let repay_amount = repay_amount.mul_floor(Decimal::from_ratio(
-                LIQUIDATION_FEE.load(deps.storage)?.denominator(),
-                Decimal::one().numerator() - LIQUIDATION_FEE.load(deps.storage)?.numerator(),
+                config.liquidation_fee.denominator(),
+                Decimal::one().numerator() - config.liquidation_fee.numerator(),
             ));
 
-            let collateral_value =
-                repay_amount.mul_floor(Decimal::from_ratio(100u128, 95u128)) + Uint128::from(1u128);
+            let collateral_value = repay_amount.mul_floor(Decimal::from_ratio(
+                net_premium.denominator(),
+                net_premium.numerator(),
+            )) + Uint128::from(1u128);
 
             let collateral_amount = Uint128::one().mul_floor(Decimal::from_ratio(
                 collateral_value * exchange_rate.denominator(),
@@ -152,7 +168,7 @@ pub fn query(deps: Deps<KujiraQuery>, _env: Env, msg: QueryMsg) -> StdResult<Bin
             let mut remaining_collateral = collateral_amount;
             let mut remaining_debt = debt_amount;
 
-            let premium_price = Decimal::from_ratio(95u128, 100u128) * exchange_rate;
+            let premium_price = net_premium * exchange_rate;
 
             let cur_collateral_value = remaining_collateral.mul_floor(premium_price);
             if cur_collateral_value.lt(&remaining_debt) {
@@ -202,7 +218,7 @@ pub fn query(deps: Deps<KujiraQuery>, _env: Env, msg: QueryMsg) -> StdResult<Bin
 
             let repay_amount = debt_amount - remaining_debt;
             let collateral_amount = collateral_amount - remaining_collateral;
-            let fee_amount = repay_amount.mul_floor(LIQUIDATION_FEE.load(deps.storage)?);
+            let fee_amount = repay_amount.mul_floor(config.liquidation_fee);
             let repay_amount = repay_amount - fee_amount;
             let res = SimulationResponse {
                 collateral_amount,
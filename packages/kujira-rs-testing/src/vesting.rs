@@ -0,0 +1,227 @@
+//! Vesting accounts created with `AuthMsg::CreateVestingAccount`, and a bank keeper that
+//! keeps their locked balances from being sent or burned
+
+use anyhow::{Error, Result as AnyResult};
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{
+    attr, from_json, Addr, Api, BalanceResponse, BankMsg, BankQuery, Binary, BlockInfo, Coin,
+    CosmosMsg, CustomMsg, CustomQuery, Decimal, Event, Querier, QueryRequest, Storage, Timestamp,
+};
+use cw_multi_test::{AppResponse, Bank, BankKeeper, BankSudo, CosmosRouter, Module};
+use cw_storage_plus::Map;
+use serde::de::DeserializeOwned;
+
+#[cw_serde]
+pub struct VestingAccount {
+    pub original: Vec<Coin>,
+    pub start_time: Timestamp,
+    pub end_time: Timestamp,
+    /// Unlocks everything at `end_time`, rather than linearly from `start_time`
+    pub delayed: bool,
+}
+
+impl VestingAccount {
+    /// The portion of `original` still locked at `time`
+    pub fn locked(&self, time: Timestamp) -> Vec<Coin> {
+        if time >= self.end_time {
+            return vec![];
+        }
+        if self.delayed || time <= self.start_time {
+            return self.original.clone();
+        }
+        let vested = Decimal::from_ratio(
+            time.seconds() - self.start_time.seconds(),
+            self.end_time.seconds() - self.start_time.seconds(),
+        );
+        self.original
+            .iter()
+            .map(|c| Coin::new(c.amount - c.amount.mul_floor(vested), &c.denom))
+            .filter(|c| !c.amount.is_zero())
+            .collect()
+    }
+}
+
+static VESTING_ACCOUNTS: Map<&Addr, VestingAccount> = Map::new("vesting_accounts");
+
+/// Loads the vesting account at `address`, if there is one
+pub fn vesting_account(storage: &dyn Storage, address: &Addr) -> AnyResult<Option<VestingAccount>> {
+    Ok(VESTING_ACCOUNTS.may_load(storage, address)?)
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn create_vesting_account<ExecC, QueryC>(
+    api: &dyn Api,
+    storage: &mut dyn Storage,
+    router: &dyn CosmosRouter<ExecC = ExecC, QueryC = QueryC>,
+    block: &BlockInfo,
+    sender: Addr,
+    to_address: Addr,
+    amount: Vec<Coin>,
+    end_time: Option<Timestamp>,
+    delayed: Option<bool>,
+) -> AnyResult<AppResponse>
+where
+    ExecC: CustomMsg + DeserializeOwned + 'static,
+    QueryC: CustomQuery + DeserializeOwned + 'static,
+{
+    let end_time = end_time.ok_or_else(|| Error::msg("end_time is required"))?;
+    if end_time <= block.time {
+        return Err(Error::msg("end_time must be in the future"));
+    }
+    if VESTING_ACCOUNTS.has(storage, &to_address) {
+        return Err(Error::msg(format!("account {to_address} already exists")));
+    }
+    let delayed = delayed.unwrap_or_default();
+
+    let mut res = router.execute(
+        api,
+        storage,
+        block,
+        sender,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: to_address.to_string(),
+            amount: amount.clone(),
+        }),
+    )?;
+    VESTING_ACCOUNTS.save(
+        storage,
+        &to_address,
+        &VestingAccount {
+            original: amount,
+            start_time: block.time,
+            end_time,
+            delayed,
+        },
+    )?;
+
+    res.events
+        .push(Event::new("create_vesting_account").add_attributes(vec![
+            attr("to_address", to_address),
+            attr("end_time", end_time.seconds().to_string()),
+            attr("delayed", delayed.to_string()),
+        ]));
+    Ok(res)
+}
+
+/// Wraps [BankKeeper], rejecting sends and burns of a vesting account's locked balance.
+/// Balance queries still return the full balance, as on chain
+#[derive(Default)]
+pub struct VestingBankKeeper(pub BankKeeper);
+
+impl VestingBankKeeper {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn init_balance(
+        &self,
+        storage: &mut dyn Storage,
+        account: &Addr,
+        amount: Vec<Coin>,
+    ) -> AnyResult<()> {
+        self.0.init_balance(storage, account, amount)
+    }
+
+    fn ensure_spendable<ExecC, QueryC>(
+        &self,
+        api: &dyn Api,
+        storage: &dyn Storage,
+        router: &dyn CosmosRouter<ExecC = ExecC, QueryC = QueryC>,
+        block: &BlockInfo,
+        sender: &Addr,
+        amount: &[Coin],
+    ) -> AnyResult<()>
+    where
+        ExecC: CustomMsg + DeserializeOwned + 'static,
+        QueryC: CustomQuery + DeserializeOwned + 'static,
+    {
+        let account = match VESTING_ACCOUNTS.may_load(storage, sender)? {
+            Some(account) => account,
+            None => return Ok(()),
+        };
+        let locked = account.locked(block.time);
+        for coin in amount {
+            let locked = locked
+                .iter()
+                .find(|c| c.denom == coin.denom)
+                .map(|c| c.amount)
+                .unwrap_or_default();
+            if locked.is_zero() {
+                continue;
+            }
+            let balance: BalanceResponse = from_json(router.query(
+                api,
+                storage,
+                block,
+                QueryRequest::Bank(BankQuery::Balance {
+                    address: sender.to_string(),
+                    denom: coin.denom.clone(),
+                }),
+            )?)?;
+            let spendable = balance.amount.amount.saturating_sub(locked);
+            if spendable < coin.amount {
+                return Err(Error::msg(format!(
+                    "spendable balance {spendable}{} is smaller than {coin}: insufficient funds",
+                    coin.denom
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Bank for VestingBankKeeper {}
+
+impl Module for VestingBankKeeper {
+    type ExecT = BankMsg;
+    type QueryT = BankQuery;
+    type SudoT = BankSudo;
+
+    fn execute<ExecC, QueryC>(
+        &self,
+        api: &dyn Api,
+        storage: &mut dyn Storage,
+        router: &dyn CosmosRouter<ExecC = ExecC, QueryC = QueryC>,
+        block: &BlockInfo,
+        sender: Addr,
+        msg: BankMsg,
+    ) -> AnyResult<AppResponse>
+    where
+        ExecC: CustomMsg + DeserializeOwned + 'static,
+        QueryC: CustomQuery + DeserializeOwned + 'static,
+    {
+        match &msg {
+            BankMsg::Send { amount, .. } | BankMsg::Burn { amount } => {
+                self.ensure_spendable(api, storage, router, block, &sender, amount)?
+            }
+            _ => {}
+        }
+        self.0.execute(api, storage, router, block, sender, msg)
+    }
+
+    fn query(
+        &self,
+        api: &dyn Api,
+        storage: &dyn Storage,
+        querier: &dyn Querier,
+        block: &BlockInfo,
+        request: BankQuery,
+    ) -> AnyResult<Binary> {
+        self.0.query(api, storage, querier, block, request)
+    }
+
+    fn sudo<ExecC, QueryC>(
+        &self,
+        api: &dyn Api,
+        storage: &mut dyn Storage,
+        router: &dyn CosmosRouter<ExecC = ExecC, QueryC = QueryC>,
+        block: &BlockInfo,
+        msg: BankSudo,
+    ) -> AnyResult<AppResponse>
+    where
+        ExecC: CustomMsg + DeserializeOwned + 'static,
+        QueryC: CustomQuery + DeserializeOwned + 'static,
+    {
+        self.0.sudo(api, storage, router, block, msg)
+    }
+}
@@ -0,0 +1,101 @@
+mod common;
+
+use common::forwarder;
+use cosmwasm_std::{coin, coins, Addr, CosmosMsg, Decimal, StakingMsg, Validator};
+use cw_multi_test::{AppResponse, Executor, StakingInfo};
+use kujira::{BatchMsg, KujiraMsg};
+use kujira_rs_testing::{
+    api::MockApiBech32,
+    mock::{mock_app, CustomApp},
+};
+
+/// A contract holding 10000 ukuji, and two validators at 10% APR and 10% commission
+fn setup() -> (CustomApp, Addr, Addr, Vec<String>) {
+    let api = MockApiBech32::new("kujira");
+    let owner = api.addr_make("owner");
+    let validators: Vec<String> = ["validator1", "validator2"]
+        .iter()
+        .map(|v| api.addr_make(v).to_string())
+        .collect();
+    let mut app = mock_app(vec![(owner.clone(), coins(10000, "ukuji"))]);
+    let block = app.block_info();
+    app.init_modules(|router, api, storage| {
+        router
+            .staking
+            .setup(
+                storage,
+                StakingInfo {
+                    bonded_denom: "ukuji".to_string(),
+                    unbonding_time: 10,
+                    apr: Decimal::percent(10),
+                },
+            )
+            .unwrap();
+        for validator in &validators {
+            router
+                .staking
+                .add_validator(
+                    api,
+                    storage,
+                    &block,
+                    Validator::new(
+                        validator.clone(),
+                        Decimal::percent(10),
+                        Decimal::percent(100),
+                        Decimal::percent(1),
+                    ),
+                )
+                .unwrap();
+        }
+    });
+    let contract = forwarder(&mut app, &owner, &coins(10000, "ukuji"));
+    (app, owner, contract, validators)
+}
+
+fn withdraw_all(app: &mut CustomApp, owner: &Addr, contract: &Addr) -> AppResponse {
+    app.execute_contract(
+        owner.clone(),
+        contract.clone(),
+        &CosmosMsg::Custom(KujiraMsg::Batch(BatchMsg::WithdrawAllDelegatorRewards {})),
+        &[],
+    )
+    .unwrap()
+}
+
+fn reward_events(res: &AppResponse) -> usize {
+    res.events
+        .iter()
+        .filter(|e| e.ty == "withdraw_delegator_reward")
+        .count()
+}
+
+#[test]
+fn withdraws_from_every_validator() {
+    let (mut app, owner, contract, validators) = setup();
+    for validator in &validators {
+        app.execute_contract(
+            owner.clone(),
+            contract.clone(),
+            &CosmosMsg::<KujiraMsg>::Staking(StakingMsg::Delegate {
+                validator: validator.clone(),
+                amount: coin(5000, "ukuji"),
+            }),
+            &[],
+        )
+        .unwrap();
+    }
+    app.update_block(|b| b.time = b.time.plus_seconds(365 * 86400));
+
+    let res = withdraw_all(&mut app, &owner, &contract);
+    assert_eq!(reward_events(&res), 2);
+    // 10% of 5000 from each, less 10% commission
+    let balance = app.wrap().query_balance(&contract, "ukuji").unwrap();
+    assert_eq!(balance.amount.u128(), 900);
+}
+
+#[test]
+fn no_delegations() {
+    let (mut app, owner, contract, _) = setup();
+    let res = withdraw_all(&mut app, &owner, &contract);
+    assert_eq!(reward_events(&res), 0);
+}
@@ -0,0 +1,129 @@
+// Each test binary compiles this module, but uses only some of its fixtures
+#![allow(dead_code)]
+
+use std::cell::RefCell;
+
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{
+    Addr, Binary, Coin, CosmosMsg, Deps, DepsMut, Empty, Env, MessageInfo, Response, StdError,
+    StdResult,
+};
+use cw_multi_test::{ContractWrapper, Executor};
+use cw_storage_plus::Item;
+use kujira::{IcaMsg, IcaRegisterVersion, IcaSudoMsg, Ics29MetadataInit, KujiraMsg, KujiraQuery};
+use kujira_rs_testing::mock::CustomApp;
+
+const REJECT: Item<bool> = Item::new("reject");
+
+thread_local! {
+    // `IcaOpenVersion` doesn't deserialize from its own serialization, so register
+    // callbacks can't be saved to and read back from contract storage. Each test runs
+    // on its own thread, so a thread local keeps their callbacks apart
+    static CALLBACKS: RefCell<Vec<IcaSudoMsg>> = const { RefCell::new(vec![]) };
+}
+
+#[cw_serde]
+pub enum ControllerMsg {
+    /// Registers an account. Built by the contract, as `IcaRegisterVersion` doesn't
+    /// deserialize from its own serialization
+    Register {
+        connection_id: String,
+        account_id: String,
+        ics29: bool,
+        callback: Option<Binary>,
+    },
+    Ica(IcaMsg),
+    /// Fail every callback from now on, until set back to false
+    Reject(bool),
+}
+
+/// Callbacks accepted by controllers on this test's thread, oldest first
+pub fn callbacks() -> Vec<IcaSudoMsg> {
+    CALLBACKS.with(|c| c.borrow().clone())
+}
+
+/// Stores and instantiates a contract that forwards [IcaMsg]s and records its callbacks
+pub fn controller(app: &mut CustomApp, owner: &Addr, funds: &[Coin]) -> Addr {
+    let code_id = app.store_code(Box::new(
+        ContractWrapper::new(execute, instantiate, query).with_sudo(sudo),
+    ));
+    app.instantiate_contract(code_id, owner.clone(), &Empty {}, funds, "controller", None)
+        .unwrap()
+}
+
+fn instantiate(
+    _deps: DepsMut<KujiraQuery>,
+    _env: Env,
+    _info: MessageInfo,
+    _msg: Empty,
+) -> StdResult<Response<KujiraMsg>> {
+    Ok(Response::default())
+}
+
+fn execute(
+    deps: DepsMut<KujiraQuery>,
+    _env: Env,
+    _info: MessageInfo,
+    msg: ControllerMsg,
+) -> StdResult<Response<KujiraMsg>> {
+    match msg {
+        ControllerMsg::Register {
+            connection_id,
+            account_id,
+            ics29,
+            callback,
+        } => {
+            let version = if ics29 {
+                IcaRegisterVersion::Ics29(Ics29MetadataInit::new(
+                    connection_id.clone(),
+                    connection_id.clone(),
+                ))
+            } else {
+                IcaRegisterVersion::Default
+            };
+            let msg = IcaMsg::Register {
+                connection_id,
+                account_id,
+                version,
+                callback,
+            };
+            Ok(Response::default().add_message(CosmosMsg::Custom(KujiraMsg::Ica(msg))))
+        }
+        ControllerMsg::Ica(msg) => {
+            Ok(Response::default().add_message(CosmosMsg::Custom(KujiraMsg::Ica(msg))))
+        }
+        ControllerMsg::Reject(reject) => {
+            REJECT.save(deps.storage, &reject)?;
+            Ok(Response::default())
+        }
+    }
+}
+
+fn sudo(deps: DepsMut<KujiraQuery>, _env: Env, msg: IcaSudoMsg) -> StdResult<Response<KujiraMsg>> {
+    if REJECT.may_load(deps.storage)?.unwrap_or_default() {
+        return Err(StdError::generic_err("Callback rejected"));
+    }
+    CALLBACKS.with(|c| c.borrow_mut().push(msg));
+    Ok(Response::default())
+}
+
+fn query(_deps: Deps<KujiraQuery>, _env: Env, _msg: Empty) -> StdResult<Binary> {
+    Err(StdError::generic_err("No queries"))
+}
+
+/// Stores and instantiates a contract that dispatches every message it is sent, so tests
+/// can send chain messages from a contract address
+pub fn forwarder(app: &mut CustomApp, owner: &Addr, funds: &[Coin]) -> Addr {
+    let code_id = app.store_code(Box::new(ContractWrapper::new(forward, instantiate, query)));
+    app.instantiate_contract(code_id, owner.clone(), &Empty {}, funds, "forwarder", None)
+        .unwrap()
+}
+
+fn forward(
+    _deps: DepsMut<KujiraQuery>,
+    _env: Env,
+    _info: MessageInfo,
+    msg: CosmosMsg<KujiraMsg>,
+) -> StdResult<Response<KujiraMsg>> {
+    Ok(Response::default().add_message(msg))
+}
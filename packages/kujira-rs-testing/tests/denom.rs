@@ -0,0 +1,163 @@
+mod common;
+
+use common::forwarder;
+use cosmwasm_std::{
+    Addr, BankQuery, CosmosMsg, DenomMetadata, DenomMetadataResponse, QueryRequest, Uint128,
+};
+use cw_multi_test::{AppResponse, Executor};
+use kujira::{Denom, DenomMsg, KujiraMsg, KujiraQuerier};
+use kujira_rs_testing::{
+    api::MockApiBech32,
+    mock::{mock_app, CustomApp},
+};
+
+struct Suite {
+    app: CustomApp,
+    owner: Addr,
+    contract: Addr,
+}
+
+impl Suite {
+    /// A forwarding contract, with a `foo` denom created
+    fn new() -> Self {
+        let owner = MockApiBech32::new("kujira").addr_make("owner");
+        let mut app = mock_app(vec![]);
+        let contract = forwarder(&mut app, &owner, &[]);
+        let mut suite = Self {
+            app,
+            owner,
+            contract,
+        };
+        suite
+            .denom(DenomMsg::Create {
+                subdenom: "foo".into(),
+            })
+            .unwrap();
+        suite
+    }
+
+    fn denom(&mut self, msg: DenomMsg) -> anyhow::Result<AppResponse> {
+        self.app.execute_contract(
+            self.owner.clone(),
+            self.contract.clone(),
+            &CosmosMsg::Custom(KujiraMsg::Denom(msg)),
+            &[],
+        )
+    }
+
+    fn foo(&self) -> Denom {
+        Denom::from(format!("factory/{}/foo", self.contract))
+    }
+}
+
+#[test]
+fn full_denom_and_admin() {
+    let suite = Suite::new();
+    let wrapper = suite.app.wrap();
+    let querier = KujiraQuerier::new(&wrapper);
+
+    let denom = querier.query_full_denom(&suite.contract, "foo").unwrap();
+    assert_eq!(denom, suite.foo());
+    assert_eq!(querier.query_denom_admin(&denom).unwrap(), suite.contract);
+
+    // Only token factory denoms have an admin
+    querier
+        .query_denom_admin(&Denom::from("ukuji"))
+        .unwrap_err();
+    querier
+        .query_denom_admin(&Denom::from(format!("factory/{}/bar", suite.contract)))
+        .unwrap_err();
+}
+
+#[test]
+fn change_admin() {
+    let mut suite = Suite::new();
+    let alice = MockApiBech32::new("kujira").addr_make("alice");
+    suite
+        .denom(DenomMsg::ChangeAdmin {
+            denom: suite.foo(),
+            address: alice.clone(),
+        })
+        .unwrap();
+
+    let wrapper = suite.app.wrap();
+    let querier = KujiraQuerier::new(&wrapper);
+    assert_eq!(querier.query_denom_admin(&suite.foo()).unwrap(), alice);
+}
+
+#[test]
+fn set_metadata() {
+    let mut suite = Suite::new();
+    let metadata = DenomMetadata {
+        base: suite.foo().to_string(),
+        symbol: "FOO".to_string(),
+        ..Default::default()
+    };
+    suite
+        .denom(DenomMsg::SetMetadata {
+            denom: suite.foo(),
+            metadata: metadata.clone(),
+        })
+        .unwrap();
+    let res: DenomMetadataResponse = suite
+        .app
+        .wrap()
+        .query(&QueryRequest::Bank(BankQuery::DenomMetadata {
+            denom: suite.foo().to_string(),
+        }))
+        .unwrap();
+    assert_eq!(res.metadata, metadata);
+
+    // The metadata must describe the denom it is set on
+    suite
+        .denom(DenomMsg::SetMetadata {
+            denom: suite.foo(),
+            metadata: DenomMetadata {
+                base: "ukuji".to_string(),
+                ..Default::default()
+            },
+        })
+        .unwrap_err();
+}
+
+#[test]
+fn force_transfer() {
+    let mut suite = Suite::new();
+    let alice = MockApiBech32::new("kujira").addr_make("alice");
+    let owner = suite.owner.clone();
+    suite
+        .denom(DenomMsg::Mint {
+            denom: suite.foo(),
+            amount: Uint128::from(100u128),
+            recipient: owner.clone(),
+        })
+        .unwrap();
+    suite
+        .denom(DenomMsg::ForceTransfer {
+            denom: suite.foo(),
+            amount: Uint128::from(40u128),
+            from_address: owner.clone(),
+            to_address: alice.clone(),
+        })
+        .unwrap();
+    let balance = |addr: &Addr| {
+        suite
+            .app
+            .wrap()
+            .query_balance(addr, suite.foo().to_string())
+            .unwrap()
+            .amount
+    };
+    assert_eq!(balance(&owner), Uint128::from(60u128));
+    assert_eq!(balance(&alice), Uint128::from(40u128));
+
+    // Only the denom admin can force a transfer
+    suite
+        .denom(DenomMsg::ForceTransfer {
+            denom: Denom::from("ukuji"),
+            amount: Uint128::from(1u128),
+            from_address: owner,
+            to_address: alice,
+        })
+        .unwrap_err();
+}
@@ -0,0 +1,509 @@
+use cosmwasm_std::{coin, coins, Addr, Coin, Decimal256, Uint128, Uint256};
+use cw20::Denom;
+use cw_multi_test::{AppResponse, ContractWrapper, Executor};
+use kujira::{
+    fin::{
+        BookResponse, ExecuteMsg, InstantiateMsg, OrderResponse, OrdersResponse, PriceResponse,
+        QueryMsg, SimulationResponse,
+    },
+    Asset, AssetInfo, Precision,
+};
+use kujira_rs_testing::{
+    api::MockApiBech32,
+    fin::{self, MockExecuteMsg, MockMsg},
+    mock::{mock_app, CustomApp},
+};
+
+const BASE: &str = "ukuji";
+const QUOTE: &str = "uusk";
+
+struct Suite {
+    app: CustomApp,
+    owner: Addr,
+    fee: Addr,
+    fin: Addr,
+}
+
+impl Suite {
+    fn new() -> Self {
+        let api = MockApiBech32::new("kujira");
+        let owner = api.addr_make("owner");
+        let fee = api.addr_make("fee");
+        let balances = ["alice", "bob", "carol", "dave"]
+            .iter()
+            .map(|name| {
+                (
+                    api.addr_make(name),
+                    vec![coin(1_000_000, BASE), coin(1_000_000, QUOTE)],
+                )
+            })
+            .collect();
+        let mut app = mock_app(balances);
+        let code_id = app.store_code(Box::new(ContractWrapper::new(
+            fin::execute,
+            fin::instantiate,
+            fin::query,
+        )));
+        let fin = app
+            .instantiate_contract(
+                code_id,
+                owner.clone(),
+                &InstantiateMsg {
+                    owner: owner.clone(),
+                    denoms: [
+                        Denom::Native(BASE.to_string()),
+                        Denom::Native(QUOTE.to_string()),
+                    ],
+                    decimal_delta: None,
+                    price_precision: Precision::DecimalPlaces(2),
+                    fee_taker: Decimal256::percent(1),
+                    fee_maker: Decimal256::permille(5),
+                    fee_address: fee.clone(),
+                },
+                &[],
+                "fin",
+                None,
+            )
+            .unwrap();
+        Self {
+            app,
+            owner,
+            fee,
+            fin,
+        }
+    }
+
+    fn launched() -> Self {
+        let mut suite = Self::new();
+        suite
+            .app
+            .execute_contract(
+                suite.owner.clone(),
+                suite.fin.clone(),
+                &ExecuteMsg::Launch {},
+                &[],
+            )
+            .unwrap();
+        suite
+    }
+
+    fn addr(&self, name: &str) -> Addr {
+        MockApiBech32::new("kujira").addr_make(name)
+    }
+
+    fn execute(&mut self, sender: &str, msg: &ExecuteMsg, funds: &[Coin]) -> AppResponse {
+        self.try_execute(sender, msg, funds).unwrap()
+    }
+
+    fn try_execute(
+        &mut self,
+        sender: &str,
+        msg: &ExecuteMsg,
+        funds: &[Coin],
+    ) -> anyhow::Result<AppResponse> {
+        let sender = self.addr(sender);
+        self.app
+            .execute_contract(sender, self.fin.clone(), msg, funds)
+    }
+
+    fn submit(&mut self, sender: &str, price: u64, offer: Coin) -> Uint128 {
+        let res = self.execute(
+            sender,
+            &ExecuteMsg::SubmitOrder {
+                price: Decimal256::percent(price),
+                callback: None,
+            },
+            &[offer],
+        );
+        let idx = res
+            .events
+            .iter()
+            .flat_map(|e| e.attributes.iter())
+            .find(|a| a.key == "order_idx")
+            .unwrap();
+        Uint128::new(idx.value.parse().unwrap())
+    }
+
+    fn swap(
+        &mut self,
+        sender: &str,
+        offer: Coin,
+        belief_price: Option<Decimal256>,
+        max_spread: Option<Decimal256>,
+    ) -> anyhow::Result<AppResponse> {
+        self.try_execute(
+            sender,
+            &ExecuteMsg::Swap {
+                offer_asset: None,
+                belief_price,
+                max_spread,
+                to: None,
+                callback: None,
+            },
+            &[offer],
+        )
+    }
+
+    fn order(&self, idx: Uint128) -> OrderResponse {
+        self.app
+            .wrap()
+            .query_wasm_smart(&self.fin, &QueryMsg::Order { order_idx: idx })
+            .unwrap()
+    }
+
+    fn balance(&self, name: &str, denom: &str) -> u128 {
+        self.balance_of(&self.addr(name), denom)
+    }
+
+    fn balance_of(&self, addr: &Addr, denom: &str) -> u128 {
+        self.app
+            .wrap()
+            .query_balance(addr, denom)
+            .unwrap()
+            .amount
+            .u128()
+    }
+}
+
+#[test]
+fn bootstrapping() {
+    let mut suite = Suite::new();
+
+    // Crossing orders rest on the book until launch
+    let ask = suite.submit("alice", 100, coin(1000, BASE));
+    let bid = suite.submit("bob", 200, coin(1000, QUOTE));
+    assert_eq!(suite.order(ask).offer_amount, Uint256::from(1000u128));
+    assert_eq!(suite.order(bid).offer_amount, Uint256::from(1000u128));
+
+    let err = suite
+        .swap("carol", coin(100, QUOTE), None, None)
+        .unwrap_err();
+    assert_eq!(
+        err.root_cause().to_string(),
+        "Generic error: Book is bootstrapping"
+    );
+
+    suite
+        .try_execute("alice", &ExecuteMsg::Launch {}, &[])
+        .unwrap_err();
+    let owner = suite.owner.clone();
+    suite
+        .app
+        .execute_contract(owner, suite.fin.clone(), &ExecuteMsg::Launch {}, &[])
+        .unwrap();
+    suite.swap("carol", coin(100, QUOTE), None, None).unwrap();
+}
+
+#[test]
+fn swap_matches_price_then_time() {
+    let mut suite = Suite::launched();
+    let alice = suite.submit("alice", 200, coin(1000, BASE));
+    let carol = suite.submit("carol", 200, coin(1000, BASE));
+    let dave = suite.submit("dave", 150, coin(1000, BASE));
+
+    let offer = Asset {
+        info: AssetInfo::NativeToken {
+            denom: QUOTE.into(),
+        },
+        amount: Uint128::new(3000),
+    };
+    let simulation: SimulationResponse = suite
+        .app
+        .wrap()
+        .query_wasm_smart(&suite.fin, &QueryMsg::Simulation { offer_asset: offer })
+        .unwrap();
+
+    // 1000 from dave at 1.5 for 1500, then 750 from alice at 2.0 for 1500
+    let res = suite.swap("bob", coin(3000, QUOTE), None, None).unwrap();
+    let attr = |key: &str| {
+        res.events
+            .iter()
+            .flat_map(|e| e.attributes.iter())
+            .find(|a| a.key == key)
+            .unwrap()
+            .value
+            .clone()
+    };
+    assert_eq!(attr("return_amount"), "1733");
+    assert_eq!(attr("commission_amount"), "17");
+    assert_eq!(attr("spread_amount"), "250");
+    assert_eq!(simulation.return_amount, Uint256::from(1733u128));
+    assert_eq!(simulation.commission_amount, Uint256::from(17u128));
+    assert_eq!(simulation.spread_amount, Uint256::from(250u128));
+
+    assert_eq!(suite.balance("bob", BASE), 1_001_733);
+    assert_eq!(suite.balance("bob", QUOTE), 997_000);
+    assert_eq!(suite.balance_of(&suite.fee.clone(), BASE), 17);
+
+    let order = suite.order(dave);
+    assert_eq!(order.offer_amount, Uint256::zero());
+    assert_eq!(order.filled_amount, Uint256::from(1500u128));
+    let order = suite.order(alice);
+    assert_eq!(order.offer_amount, Uint256::from(250u128));
+    assert_eq!(order.filled_amount, Uint256::from(1500u128));
+    let order = suite.order(carol);
+    assert_eq!(order.offer_amount, Uint256::from(1000u128));
+    assert_eq!(order.filled_amount, Uint256::zero());
+
+    // The maker fee is taken on withdrawal
+    suite.execute(
+        "alice",
+        &ExecuteMsg::WithdrawOrders {
+            order_idxs: None,
+            callback: None,
+        },
+        &[],
+    );
+    assert_eq!(suite.balance("alice", QUOTE), 1_001_493);
+    assert_eq!(suite.balance_of(&suite.fee.clone(), QUOTE), 7);
+    assert_eq!(suite.order(alice).filled_amount, Uint256::zero());
+
+    // A fully filled and withdrawn order is removed
+    suite.execute(
+        "dave",
+        &ExecuteMsg::WithdrawOrders {
+            order_idxs: Some(vec![dave]),
+            callback: None,
+        },
+        &[],
+    );
+    let orders: OrdersResponse = suite
+        .app
+        .wrap()
+        .query_wasm_smart(
+            &suite.fin,
+            &QueryMsg::OrdersByUser {
+                address: suite.addr("dave"),
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap();
+    assert!(orders.orders.is_empty());
+}
+
+#[test]
+fn crossing_order_takes_then_rests() {
+    let mut suite = Suite::launched();
+    suite.submit("alice", 200, coin(100, BASE));
+
+    // Takes 100 for 200 at 2.0, and the remaining 300 rests at 2.5
+    let bid = suite.submit("bob", 250, coin(500, QUOTE));
+    assert_eq!(suite.balance("bob", BASE), 1_000_099);
+    let order = suite.order(bid);
+    assert_eq!(order.offer_amount, Uint256::from(300u128));
+    assert_eq!(order.original_offer_amount, Uint256::from(500u128));
+    assert_eq!(order.filled_amount, Uint256::zero());
+
+    let book: BookResponse = suite
+        .app
+        .wrap()
+        .query_wasm_smart(
+            &suite.fin,
+            &QueryMsg::Book {
+                limit: None,
+                offset: None,
+            },
+        )
+        .unwrap();
+    assert!(book.base.is_empty());
+    assert_eq!(book.quote.len(), 1);
+    assert_eq!(book.quote[0].quote_price, Decimal256::percent(250));
+    assert_eq!(book.quote[0].total_offer_amount, Uint256::from(300u128));
+}
+
+#[test]
+fn max_spread() {
+    let mut suite = Suite::launched();
+    let err = suite.swap("bob", coin(100, QUOTE), None, None).unwrap_err();
+    assert_eq!(
+        err.root_cause().to_string(),
+        "Generic error: Insufficient liquidity"
+    );
+
+    suite.submit("alice", 100, coin(1000, BASE));
+    suite.submit("alice", 200, coin(1000, BASE));
+
+    // 3000 returns 2000 rather than 3000 at the best price, a third off
+    for belief_price in [Some(Decimal256::one()), None] {
+        let err = suite
+            .swap(
+                "bob",
+                coin(3000, QUOTE),
+                belief_price,
+                Some(Decimal256::percent(10)),
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.root_cause().to_string(),
+            "Generic error: Max spread assertion"
+        );
+    }
+    suite
+        .swap(
+            "bob",
+            coin(3000, QUOTE),
+            Some(Decimal256::one()),
+            Some(Decimal256::percent(50)),
+        )
+        .unwrap();
+    assert_eq!(suite.balance("bob", BASE), 1_001_980);
+}
+
+#[test]
+fn book_and_price_paging() {
+    let mut suite = Suite::launched();
+    for price in [130, 110, 120] {
+        suite.submit("alice", price, coin(1000, BASE));
+    }
+    for price in [80, 90] {
+        suite.submit("bob", price, coin(1000, QUOTE));
+    }
+    suite.submit("carol", 120, coin(500, BASE));
+
+    let book = |limit, offset| -> (Vec<Decimal256>, Vec<Decimal256>) {
+        let book: BookResponse = suite
+            .app
+            .wrap()
+            .query_wasm_smart(
+                &suite.fin,
+                &QueryMsg::Book {
+                    limit: Some(limit),
+                    offset: Some(offset),
+                },
+            )
+            .unwrap();
+        let prices =
+            |pools: Vec<kujira::fin::PoolResponse>| pools.iter().map(|p| p.quote_price).collect();
+        (prices(book.base), prices(book.quote))
+    };
+    let pct = |prices: &[u64]| prices.iter().map(|p| Decimal256::percent(*p)).collect();
+    assert_eq!(book(2, 0), (pct(&[110, 120]), pct(&[90, 80])));
+    assert_eq!(book(2, 1), (pct(&[120, 130]), pct(&[80])));
+
+    let price: PriceResponse = suite
+        .app
+        .wrap()
+        .query_wasm_smart(
+            &suite.fin,
+            &QueryMsg::Price {
+                price: Decimal256::percent(120),
+            },
+        )
+        .unwrap();
+    assert_eq!(price.pools[0].total_offer_amount, Uint256::from(1500u128));
+    assert_eq!(price.pools[1].total_offer_amount, Uint256::zero());
+}
+
+#[test]
+fn retract() {
+    let mut suite = Suite::launched();
+    let idx = suite.submit("alice", 200, coin(1000, BASE));
+    suite.swap("bob", coin(1000, QUOTE), None, None).unwrap();
+
+    suite
+        .try_execute(
+            "bob",
+            &ExecuteMsg::RetractOrder {
+                order_idx: idx,
+                amount: None,
+                callback: None,
+            },
+            &[],
+        )
+        .unwrap_err();
+
+    // 200 of the unfilled 500, plus the 1000 filled less the maker fee
+    suite.execute(
+        "alice",
+        &ExecuteMsg::RetractOrder {
+            order_idx: idx,
+            amount: Some(Uint256::from(200u128)),
+            callback: None,
+        },
+        &[],
+    );
+    assert_eq!(suite.balance("alice", BASE), 999_200);
+    assert_eq!(suite.balance("alice", QUOTE), 1_000_995);
+    let order = suite.order(idx);
+    assert_eq!(order.offer_amount, Uint256::from(300u128));
+    assert_eq!(order.filled_amount, Uint256::zero());
+
+    suite.execute(
+        "alice",
+        &ExecuteMsg::RetractOrders {
+            order_idxs: vec![idx],
+            callback: None,
+        },
+        &[],
+    );
+    assert_eq!(suite.balance("alice", BASE), 999_500);
+    let fin = suite.fin.clone();
+    suite
+        .app
+        .wrap()
+        .query_wasm_smart::<OrderResponse>(fin, &QueryMsg::Order { order_idx: idx })
+        .unwrap_err();
+}
+
+#[test]
+fn price_validation() {
+    let mut suite = Suite::launched();
+    for (price, error) in [
+        (
+            Decimal256::permille(1234),
+            "Generic error: Invalid price precision",
+        ),
+        (
+            Decimal256::zero(),
+            "Generic error: Price must be greater than zero",
+        ),
+    ] {
+        let err = suite
+            .try_execute(
+                "alice",
+                &ExecuteMsg::SubmitOrder {
+                    price,
+                    callback: None,
+                },
+                &coins(1000, BASE),
+            )
+            .unwrap_err();
+        assert_eq!(err.root_cause().to_string(), error);
+    }
+}
+
+#[test]
+fn mock_fill() {
+    let mut suite = Suite::launched();
+    let idx = suite.submit("alice", 200, coin(1000, QUOTE));
+    let alice = suite.addr("alice");
+    suite
+        .app
+        .execute_contract(
+            alice,
+            suite.fin.clone(),
+            &MockExecuteMsg::Mock(MockMsg::Fill { idx }),
+            &[],
+        )
+        .unwrap();
+    let order = suite.order(idx);
+    assert_eq!(order.offer_amount, Uint256::zero());
+    assert_eq!(order.filled_amount, Uint256::from(500u128));
+
+    // A mock fill has no counterparty, so the contract is funded directly
+    let (carol, fin) = (suite.addr("carol"), suite.fin.clone());
+    suite
+        .app
+        .send_tokens(carol, fin, &coins(500, BASE))
+        .unwrap();
+    suite.execute(
+        "alice",
+        &ExecuteMsg::WithdrawOrders {
+            order_idxs: None,
+            callback: None,
+        },
+        &[],
+    );
+    assert_eq!(suite.balance("alice", BASE), 1_000_498);
+}
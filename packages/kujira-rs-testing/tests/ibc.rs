@@ -0,0 +1,84 @@
+use cosmwasm_std::{Binary, QueryRequest, StdResult};
+use kujira::{IbcVerifyQuery, IbcVerifyResponse, KujiraQuery};
+use kujira_rs_testing::mock::{mock_app, sudo, CustomApp, KujiraSudo};
+
+const CONNECTION: &str = "connection-0";
+
+fn verify(app: &CustomApp, query: IbcVerifyQuery) -> StdResult<IbcVerifyResponse> {
+    app.wrap()
+        .query(&QueryRequest::Custom(KujiraQuery::Ibc(query)))
+}
+
+fn membership(app: &CustomApp, height: u64, value: &[u8]) -> bool {
+    verify(
+        app,
+        IbcVerifyQuery::VerifyMembership {
+            connection: CONNECTION.to_string(),
+            revision_number: 1,
+            revision_height: height,
+            proof: Binary::default(),
+            value: Binary::from(value),
+            path_prefix: "bank".to_string(),
+            path_key: Binary::from(b"key"),
+        },
+    )
+    .is_ok()
+}
+
+fn non_membership(app: &CustomApp, height: u64) -> bool {
+    verify(
+        app,
+        IbcVerifyQuery::VerifyNonMembership {
+            connection: CONNECTION.to_string(),
+            revision_number: 1,
+            revision_height: height,
+            proof: Binary::default(),
+            path_prefix: "bank".to_string(),
+            path_key: Binary::from(b"key"),
+        },
+    )
+    .is_ok()
+}
+
+fn set_remote_state(app: &mut CustomApp, height: u64, value: Option<&[u8]>) {
+    sudo(
+        app,
+        KujiraSudo::SetRemoteState {
+            connection: CONNECTION.to_string(),
+            revision_number: 1,
+            revision_height: height,
+            path_prefix: "bank".to_string(),
+            path_key: Binary::from(b"key"),
+            value: value.map(Binary::from),
+        },
+    )
+    .unwrap();
+}
+
+#[test]
+fn unset_key_is_absent() {
+    let app = mock_app(vec![]);
+    assert!(non_membership(&app, 5));
+    assert!(!membership(&app, 5, b"value"));
+}
+
+#[test]
+fn proofs_follow_remote_height() {
+    let mut app = mock_app(vec![]);
+    set_remote_state(&mut app, 10, Some(b"value"));
+    set_remote_state(&mut app, 20, None);
+
+    // Before the write
+    assert!(non_membership(&app, 9));
+    assert!(!membership(&app, 9, b"value"));
+
+    // The latest write at or below the proof height applies
+    assert!(membership(&app, 10, b"value"));
+    assert!(membership(&app, 15, b"value"));
+    assert!(!membership(&app, 15, b"other"));
+    assert!(!non_membership(&app, 15));
+
+    // After the delete
+    assert!(non_membership(&app, 25));
+    assert!(!membership(&app, 25, b"value"));
+}
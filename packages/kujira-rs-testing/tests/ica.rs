@@ -0,0 +1,236 @@
+mod common;
+
+use common::{callbacks, controller, ControllerMsg};
+use cosmwasm_std::{Addr, Binary, QueryRequest, StdResult};
+use cw_multi_test::{AppResponse, Executor};
+use kujira::{
+    AccountAddressResponse, IcaMsg, IcaOpenVersion, IcaQuery, IcaRegisterCallbackData,
+    IcaRegisterResult, IcaSudoMsg, IcaTxCallbackData, IcaTxResult, KujiraQuery, ProtobufAny,
+};
+use kujira_rs_testing::{
+    ica::{self, IcaAccountStatus, IcaRegisterOutcome},
+    mock::{mock_app, sudo, CustomApp, KujiraSudo},
+};
+
+const CONNECTION: &str = "connection-0";
+const ACCOUNT: &str = "account";
+
+fn register(app: &mut CustomApp, owner: &Addr, contract: &Addr, ics29: bool) {
+    app.execute_contract(
+        owner.clone(),
+        contract.clone(),
+        &ControllerMsg::Register {
+            connection_id: CONNECTION.to_string(),
+            account_id: ACCOUNT.to_string(),
+            ics29,
+            callback: Some(Binary::from(b"register")),
+        },
+        &[],
+    )
+    .unwrap();
+}
+
+fn submit(app: &mut CustomApp, owner: &Addr, contract: &Addr) -> anyhow::Result<AppResponse> {
+    app.execute_contract(
+        owner.clone(),
+        contract.clone(),
+        &ControllerMsg::Ica(IcaMsg::Submit {
+            connection_id: CONNECTION.to_string(),
+            account_id: ACCOUNT.to_string(),
+            msgs: vec![ProtobufAny::new(
+                "/cosmos.bank.v1beta1.MsgSend",
+                Binary::from(b"send"),
+            )],
+            memo: "memo".to_string(),
+            timeout: 60,
+            callback: Some(Binary::from(b"submit")),
+        }),
+        &[],
+    )
+}
+
+fn resolve_register(
+    app: &mut CustomApp,
+    contract: &Addr,
+    result: IcaRegisterOutcome,
+) -> anyhow::Result<AppResponse> {
+    sudo(
+        app,
+        KujiraSudo::IcaRegister {
+            owner: contract.clone(),
+            connection_id: CONNECTION.to_string(),
+            account_id: ACCOUNT.to_string(),
+            result,
+        },
+    )
+}
+
+fn resolve_tx(
+    app: &mut CustomApp,
+    contract: &Addr,
+    sequence: u64,
+    result: IcaTxResult,
+) -> anyhow::Result<AppResponse> {
+    sudo(
+        app,
+        KujiraSudo::IcaTx {
+            owner: contract.clone(),
+            connection_id: CONNECTION.to_string(),
+            account_id: ACCOUNT.to_string(),
+            sequence,
+            result,
+        },
+    )
+}
+
+fn account_address(app: &CustomApp, contract: &Addr) -> StdResult<String> {
+    app.wrap()
+        .query::<AccountAddressResponse>(&QueryRequest::Custom(KujiraQuery::Ica(
+            IcaQuery::AccountAddress {
+                owner: contract.clone(),
+                connection_id: CONNECTION.to_string(),
+                account_id: ACCOUNT.to_string(),
+            },
+        )))
+        .map(|res| res.address)
+}
+
+fn status(app: &CustomApp, contract: &Addr) -> IcaAccountStatus {
+    ica::account(app.storage(), contract, CONNECTION, ACCOUNT)
+        .unwrap()
+        .unwrap()
+        .status
+}
+
+fn setup() -> (CustomApp, Addr, Addr) {
+    let mut app = mock_app(vec![]);
+    let owner = app.api().addr_make("owner");
+    let contract = controller(&mut app, &owner, &[]);
+    (app, owner, contract)
+}
+
+#[test]
+fn register_success() {
+    let (mut app, owner, contract) = setup();
+    register(&mut app, &owner, &contract, true);
+    assert_eq!(status(&app, &contract), IcaAccountStatus::Pending);
+    account_address(&app, &contract).unwrap_err();
+    submit(&mut app, &owner, &contract).unwrap_err();
+
+    resolve_register(&mut app, &contract, IcaRegisterOutcome::Success {}).unwrap();
+    let address = ica::account_address(&contract, CONNECTION, ACCOUNT);
+    assert_eq!(account_address(&app, &contract).unwrap(), address);
+    match callbacks().as_slice() {
+        [IcaSudoMsg::IcaRegisterCallback(IcaRegisterCallbackData {
+            callback,
+            result:
+                IcaRegisterResult::Success {
+                    data: IcaOpenVersion::Ics29(version),
+                },
+            ..
+        })] => {
+            assert_eq!(callback, &Some(Binary::from(b"register")));
+            assert_eq!(version.app_version.address, address);
+        }
+        callbacks => panic!("unexpected callbacks {:?}", callbacks),
+    }
+
+    // Only a pending registration can be resolved
+    resolve_register(&mut app, &contract, IcaRegisterOutcome::Success {}).unwrap_err();
+}
+
+#[test]
+fn register_error() {
+    let (mut app, owner, contract) = setup();
+    register(&mut app, &owner, &contract, false);
+    resolve_register(
+        &mut app,
+        &contract,
+        IcaRegisterOutcome::Error {
+            error: "handshake failed".to_string(),
+        },
+    )
+    .unwrap();
+    assert_eq!(status(&app, &contract), IcaAccountStatus::Closed);
+    assert!(matches!(
+        callbacks().as_slice(),
+        [IcaSudoMsg::IcaRegisterCallback(IcaRegisterCallbackData {
+            result: IcaRegisterResult::Error { .. },
+            ..
+        })]
+    ));
+}
+
+#[test]
+fn submit_and_resolve() {
+    let (mut app, owner, contract) = setup();
+    register(&mut app, &owner, &contract, false);
+    resolve_register(&mut app, &contract, IcaRegisterOutcome::Success {}).unwrap();
+    submit(&mut app, &owner, &contract).unwrap();
+    submit(&mut app, &owner, &contract).unwrap();
+    let txs = ica::txs(app.storage(), &contract, CONNECTION, ACCOUNT).unwrap();
+    assert_eq!(txs.iter().map(|(seq, _)| *seq).collect::<Vec<_>>(), [1, 2]);
+
+    resolve_tx(
+        &mut app,
+        &contract,
+        2,
+        IcaTxResult::Success {
+            data: Binary::from(b"ok"),
+        },
+    )
+    .unwrap();
+    match callbacks().last() {
+        Some(IcaSudoMsg::IcaTxCallback(IcaTxCallbackData {
+            sequence, callback, ..
+        })) => {
+            assert_eq!(*sequence, 2);
+            assert_eq!(callback, &Some(Binary::from(b"submit")));
+        }
+        callback => panic!("unexpected callback {:?}", callback),
+    }
+    // A tx can only be resolved once
+    resolve_tx(&mut app, &contract, 2, IcaTxResult::Timeout {}).unwrap_err();
+
+    // A timeout closes the channel
+    resolve_tx(&mut app, &contract, 1, IcaTxResult::Timeout {}).unwrap();
+    assert_eq!(status(&app, &contract), IcaAccountStatus::Closed);
+    account_address(&app, &contract).unwrap_err();
+
+    // Registering again reopens the account and continues its sequence
+    register(&mut app, &owner, &contract, false);
+    resolve_register(&mut app, &contract, IcaRegisterOutcome::Success {}).unwrap();
+    submit(&mut app, &owner, &contract).unwrap();
+    let txs = ica::txs(app.storage(), &contract, CONNECTION, ACCOUNT).unwrap();
+    assert_eq!(txs.last().unwrap().0, 3);
+}
+
+#[test]
+fn rejected_callback_rolls_back() {
+    let (mut app, owner, contract) = setup();
+    register(&mut app, &owner, &contract, false);
+    let reject = |app: &mut CustomApp, reject: bool| {
+        app.execute_contract(
+            owner.clone(),
+            contract.clone(),
+            &ControllerMsg::Reject(reject),
+            &[],
+        )
+        .unwrap();
+    };
+
+    reject(&mut app, true);
+    resolve_register(&mut app, &contract, IcaRegisterOutcome::Success {}).unwrap_err();
+    assert_eq!(status(&app, &contract), IcaAccountStatus::Pending);
+    reject(&mut app, false);
+    resolve_register(&mut app, &contract, IcaRegisterOutcome::Success {}).unwrap();
+    assert_eq!(status(&app, &contract), IcaAccountStatus::Open);
+
+    submit(&mut app, &owner, &contract).unwrap();
+    reject(&mut app, true);
+    resolve_tx(&mut app, &contract, 1, IcaTxResult::Timeout {}).unwrap_err();
+    assert_eq!(status(&app, &contract), IcaAccountStatus::Open);
+    let txs = ica::txs(app.storage(), &contract, CONNECTION, ACCOUNT).unwrap();
+    assert_eq!(txs[0].1.result, None);
+    assert_eq!(callbacks().len(), 1);
+}
@@ -0,0 +1,32 @@
+use cosmwasm_std::Decimal;
+use kujira::KujiraQuerier;
+use kujira_rs_testing::mock::{mock_app, sudo, KujiraSudo};
+
+#[test]
+fn query_exchange_rates() {
+    let mut app = mock_app(vec![]);
+    sudo(
+        &mut app,
+        KujiraSudo::SetOraclePrice {
+            denom: "ukuji".to_string(),
+            price: Decimal::percent(75),
+        },
+    )
+    .unwrap();
+
+    let wrapper = app.wrap();
+    let querier = KujiraQuerier::new(&wrapper);
+    let rates = querier
+        .query_exchange_rates(&["factory/owner/coll", "ukuji", "unknown"])
+        .unwrap();
+    assert_eq!(rates.len(), 3);
+    assert_eq!(
+        Decimal::from(rates["factory/owner/coll"]),
+        Decimal::from_ratio(1425u128, 100u128)
+    );
+    assert_eq!(Decimal::from(rates["ukuji"]), Decimal::percent(75));
+    assert_eq!(Decimal::from(rates["unknown"]), Decimal::zero());
+
+    let empty: [&str; 0] = [];
+    assert!(querier.query_exchange_rates(&empty).unwrap().is_empty());
+}
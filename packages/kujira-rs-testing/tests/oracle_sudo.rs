@@ -0,0 +1,73 @@
+use cosmwasm_std::{Decimal, QueryRequest};
+use kujira::{ExchangeRateResponse, KujiraQuery, OracleQuery};
+use kujira_rs_testing::mock::{mock_app, sudo, CustomApp, KujiraSudo};
+
+fn exchange_rate(app: &CustomApp, denom: &str) -> Decimal {
+    let res: ExchangeRateResponse = app
+        .wrap()
+        .query(&QueryRequest::Custom(KujiraQuery::Oracle(
+            OracleQuery::ExchangeRate {
+                denom: denom.to_string(),
+            },
+        )))
+        .unwrap();
+    res.rate
+}
+
+#[test]
+fn set_and_remove_price() {
+    let mut app = mock_app(vec![]);
+    assert_eq!(
+        exchange_rate(&app, "factory/contract0/uusk"),
+        Decimal::one()
+    );
+
+    sudo(
+        &mut app,
+        KujiraSudo::SetOraclePrice {
+            denom: "factory/contract0/uusk".to_string(),
+            price: Decimal::percent(50),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        exchange_rate(&app, "factory/contract0/uusk"),
+        Decimal::percent(50)
+    );
+
+    sudo(
+        &mut app,
+        KujiraSudo::RemoveOraclePrice {
+            denom: "factory/contract0/uusk".to_string(),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        exchange_rate(&app, "factory/contract0/uusk"),
+        Decimal::zero()
+    );
+}
+
+#[test]
+fn set_many_prices() {
+    let mut app = mock_app(vec![]);
+    let res = sudo(
+        &mut app,
+        KujiraSudo::SetOraclePrices {
+            prices: vec![
+                ("ukuji".to_string(), Decimal::one()),
+                ("uatom".to_string(), Decimal::percent(2)),
+            ],
+        },
+    )
+    .unwrap();
+    assert_eq!(res.events.len(), 2);
+    assert_eq!(exchange_rate(&app, "ukuji"), Decimal::one());
+    assert_eq!(exchange_rate(&app, "uatom"), Decimal::percent(2));
+
+    // Prices set before the app was built are unaffected
+    assert_eq!(
+        exchange_rate(&app, "factory/owner/coll"),
+        Decimal::from_ratio(1425u128, 100u128)
+    );
+}
@@ -0,0 +1,94 @@
+use cosmwasm_std::{Addr, Decimal, Uint128};
+use cw_multi_test::{ContractWrapper, Executor};
+use kujira::{
+    orca::{InstantiateMsg, QueryMsg, SimulationResponse},
+    Denom,
+};
+use kujira_rs_testing::{
+    mock::{mock_app, CustomApp},
+    orca,
+};
+
+fn setup(max_slot: u8) -> (CustomApp, Addr) {
+    let mut app = mock_app(vec![]);
+    let code_id = app.store_code(Box::new(ContractWrapper::new(
+        orca::execute,
+        orca::instantiate,
+        orca::query,
+    )));
+    let owner = Addr::unchecked("owner");
+    let contract = app
+        .instantiate_contract(
+            code_id,
+            owner.clone(),
+            &InstantiateMsg {
+                owner,
+                bid_denom: Denom::from("uusk"),
+                collateral_denom: Denom::from("ukuji"),
+                bid_threshold: Uint128::from(1000000u128),
+                max_slot,
+                premium_rate_per_slot: Decimal::percent(1),
+                waiting_period: 600,
+                liquidation_fee: Decimal::percent(1),
+                withdrawal_fee: Decimal::permille(5),
+                fee_address: Addr::unchecked("fee"),
+            },
+            &[],
+            "orca",
+            None,
+        )
+        .unwrap();
+    (app, contract)
+}
+
+#[test]
+#[allow(deprecated)]
+fn highest_slot_premium() {
+    let (app, contract) = setup(20);
+
+    // Slots 0 to 19, so the top premium is 19%
+    let res: SimulationResponse = app
+        .wrap()
+        .query_wasm_smart(
+            &contract,
+            &QueryMsg::Simulate {
+                collateral_amount: Uint128::from(1000u128),
+                repay_denom: None,
+                exchange_rate: Decimal::one(),
+            },
+        )
+        .unwrap();
+    assert_eq!(res.repay_amount, Uint128::from(802u128));
+
+    let res: SimulationResponse = app
+        .wrap()
+        .query_wasm_smart(
+            &contract,
+            &QueryMsg::SimulateReverse {
+                repay_amount: Uint128::from(802u128),
+                repay_denom: None,
+                exchange_rate: Decimal::one(),
+            },
+        )
+        .unwrap();
+    assert_eq!(res.collateral_amount, Uint128::from(1001u128));
+}
+
+#[test]
+#[allow(deprecated)]
+fn full_premium_reverse() {
+    let (app, contract) = setup(101);
+
+    let err = app
+        .wrap()
+        .query_wasm_smart::<SimulationResponse>(
+            &contract,
+            &QueryMsg::SimulateReverse {
+                repay_amount: Uint128::from(1000u128),
+                repay_denom: None,
+                exchange_rate: Decimal::one(),
+            },
+        )
+        .unwrap_err();
+    assert!(err.to_string().contains("100% premium"));
+}
@@ -0,0 +1,149 @@
+mod common;
+
+use common::{callbacks, controller, ControllerMsg};
+use cosmwasm_std::{coin, Addr, Binary, IbcTimeout, Timestamp};
+use cw_multi_test::{AppResponse, Executor};
+use kujira::{IcaMsg, IcaSudoMsg, IcaTxResult, TransferCallbackData, TransferReceiptData};
+use kujira_rs_testing::{
+    api::MockApiBech32,
+    ica,
+    mock::{mock_app, sudo, CustomApp, KujiraSudo},
+};
+
+const CHANNEL: &str = "channel-1";
+
+fn setup() -> (CustomApp, Addr, Addr) {
+    let owner = MockApiBech32::new("kujira").addr_make("owner");
+    let mut app = mock_app(vec![(owner.clone(), vec![coin(1000, "ukuji")])]);
+    let contract = controller(&mut app, &owner, &[coin(1000, "ukuji")]);
+    (app, owner, contract)
+}
+
+fn transfer(app: &mut CustomApp, owner: &Addr, contract: &Addr) {
+    app.execute_contract(
+        owner.clone(),
+        contract.clone(),
+        &ControllerMsg::Ica(IcaMsg::Transfer {
+            channel_id: CHANNEL.to_string(),
+            to_address: "cosmos1receiver".to_string(),
+            amount: coin(100, "ukuji"),
+            timeout: IbcTimeout::with_timestamp(Timestamp::from_seconds(1)),
+            callback: Binary::from(b"transfer"),
+        }),
+        &[],
+    )
+    .unwrap();
+}
+
+fn resolve(app: &mut CustomApp, sequence: u64, result: IcaTxResult) -> anyhow::Result<AppResponse> {
+    sudo(
+        app,
+        KujiraSudo::IbcTransfer {
+            channel_id: CHANNEL.to_string(),
+            sequence,
+            result,
+        },
+    )
+}
+
+fn receive(app: &mut CustomApp, receiver: &Addr) -> anyhow::Result<AppResponse> {
+    sudo(
+        app,
+        KujiraSudo::IbcTransferReceipt {
+            channel_id: "channel-2".to_string(),
+            sender: "cosmos1sender".to_string(),
+            receiver: receiver.clone(),
+            amount: coin(5, "ibc/ABC"),
+            memo: "memo".to_string(),
+        },
+    )
+}
+
+fn balance(app: &CustomApp, addr: &Addr, denom: &str) -> u128 {
+    app.wrap().query_balance(addr, denom).unwrap().amount.u128()
+}
+
+#[test]
+fn transfer_callbacks() {
+    let (mut app, owner, contract) = setup();
+    transfer(&mut app, &owner, &contract);
+    transfer(&mut app, &owner, &contract);
+    let escrow = ica::escrow_address(CHANNEL);
+    assert_eq!(balance(&app, &contract, "ukuji"), 800);
+    assert_eq!(balance(&app, &escrow, "ukuji"), 200);
+
+    resolve(
+        &mut app,
+        1,
+        IcaTxResult::Success {
+            data: Binary::default(),
+        },
+    )
+    .unwrap();
+    match callbacks().as_slice() {
+        [IcaSudoMsg::TransferCallback(TransferCallbackData {
+            sequence,
+            receiver,
+            amount,
+            callback,
+            ..
+        })] => {
+            assert_eq!(*sequence, 1);
+            assert_eq!(receiver, "cosmos1receiver");
+            assert_eq!(amount, "100");
+            assert_eq!(callback, &Binary::from(b"transfer"));
+        }
+        callbacks => panic!("unexpected callbacks {:?}", callbacks),
+    }
+
+    // A timed out transfer is refunded from escrow
+    resolve(&mut app, 2, IcaTxResult::Timeout {}).unwrap();
+    assert_eq!(balance(&app, &contract, "ukuji"), 900);
+    assert_eq!(balance(&app, &escrow, "ukuji"), 100);
+    resolve(&mut app, 2, IcaTxResult::Timeout {}).unwrap_err();
+    assert_eq!(ica::transfers(app.storage(), CHANNEL).unwrap().len(), 2);
+}
+
+#[test]
+fn transfer_receipt() {
+    let (mut app, owner, contract) = setup();
+    receive(&mut app, &contract).unwrap();
+    assert_eq!(balance(&app, &contract, "ibc/ABC"), 5);
+    match callbacks().as_slice() {
+        [IcaSudoMsg::TransferReceipt(TransferReceiptData {
+            sequence, sender, ..
+        })] => {
+            assert_eq!(*sequence, 1);
+            assert_eq!(sender, "cosmos1sender");
+        }
+        callbacks => panic!("unexpected callbacks {:?}", callbacks),
+    }
+
+    // Accounts are credited without a callback
+    receive(&mut app, &owner).unwrap();
+    assert_eq!(balance(&app, &owner, "ibc/ABC"), 5);
+    assert_eq!(callbacks().len(), 1);
+}
+
+#[test]
+fn rejected_callback_rolls_back() {
+    let (mut app, owner, contract) = setup();
+    transfer(&mut app, &owner, &contract);
+    app.execute_contract(
+        owner.clone(),
+        contract.clone(),
+        &ControllerMsg::Reject(true),
+        &[],
+    )
+    .unwrap();
+
+    resolve(&mut app, 1, IcaTxResult::Timeout {}).unwrap_err();
+    assert_eq!(balance(&app, &contract, "ukuji"), 900);
+    assert_eq!(balance(&app, &ica::escrow_address(CHANNEL), "ukuji"), 100);
+    let transfers = ica::transfers(app.storage(), CHANNEL).unwrap();
+    assert_eq!(transfers[0].1.result, None);
+
+    receive(&mut app, &contract).unwrap_err();
+    assert_eq!(balance(&app, &contract, "ibc/ABC"), 0);
+    assert!(callbacks().is_empty());
+}
@@ -0,0 +1,137 @@
+mod common;
+
+use common::forwarder;
+use cosmwasm_std::{coin, coins, Addr, BankMsg, CosmosMsg, Timestamp};
+use cw_multi_test::{AppResponse, Executor};
+use kujira::{AuthMsg, KujiraMsg};
+use kujira_rs_testing::{
+    api::MockApiBech32,
+    mock::{mock_app, CustomApp},
+    vesting,
+};
+
+struct Suite {
+    app: CustomApp,
+    owner: Addr,
+    bob: Addr,
+    carol: Addr,
+    start: Timestamp,
+}
+
+impl Suite {
+    /// Vests 1000 ukuji to bob over 100 seconds, and sends him 10 ukuji unlocked
+    fn new(delayed: bool) -> Self {
+        let api = MockApiBech32::new("kujira");
+        let owner = api.addr_make("owner");
+        let bob = api.addr_make("bob");
+        let carol = api.addr_make("carol");
+        let mut app = mock_app(vec![(owner.clone(), coins(2000, "ukuji"))]);
+        let contract = forwarder(&mut app, &owner, &coins(1000, "ukuji"));
+        let start = app.block_info().time;
+        app.execute_contract(
+            owner.clone(),
+            contract,
+            &CosmosMsg::Custom(KujiraMsg::Auth(AuthMsg::CreateVestingAccount {
+                to_address: bob.clone(),
+                amount: coins(1000, "ukuji"),
+                end_time: Some(start.plus_seconds(100)),
+                delayed: Some(delayed),
+            })),
+            &[],
+        )
+        .unwrap();
+        app.send_tokens(owner.clone(), bob.clone(), &coins(10, "ukuji"))
+            .unwrap();
+        Self {
+            app,
+            owner,
+            bob,
+            carol,
+            start,
+        }
+    }
+
+    fn send(&mut self, amount: u128) -> anyhow::Result<AppResponse> {
+        self.app.execute(
+            self.bob.clone(),
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: self.carol.to_string(),
+                amount: coins(amount, "ukuji"),
+            }),
+        )
+    }
+
+    fn wait(&mut self, seconds: u64) {
+        let time = self.start.plus_seconds(seconds);
+        self.app.update_block(|b| b.time = time);
+    }
+
+    fn balance(&self) -> u128 {
+        self.app
+            .wrap()
+            .query_balance(&self.bob, "ukuji")
+            .unwrap()
+            .amount
+            .u128()
+    }
+}
+
+#[test]
+fn continuous_vesting() {
+    let mut suite = Suite::new(false);
+    // The full balance is reported, but only the unlocked part can be spent
+    assert_eq!(suite.balance(), 1010);
+    suite.send(11).unwrap_err();
+    suite.send(10).unwrap();
+
+    suite.wait(30);
+    let account = vesting::vesting_account(suite.app.storage(), &suite.bob)
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        account.locked(suite.app.block_info().time),
+        coins(700, "ukuji")
+    );
+    suite.send(301).unwrap_err();
+    suite.send(300).unwrap();
+
+    suite.wait(100);
+    suite.send(700).unwrap();
+    assert_eq!(suite.balance(), 0);
+}
+
+#[test]
+fn delayed_vesting() {
+    let mut suite = Suite::new(true);
+    suite.send(10).unwrap();
+
+    suite.wait(99);
+    suite.send(1).unwrap_err();
+
+    suite.wait(100);
+    suite.send(1000).unwrap();
+}
+
+#[test]
+fn create_vesting_account_validation() {
+    let mut suite = Suite::new(false);
+    let contract = forwarder(&mut suite.app, &suite.owner, &coins(500, "ukuji"));
+    let (bob, carol, start) = (suite.bob.clone(), suite.carol.clone(), suite.start);
+    let mut create = |to_address: &Addr, end_time: Option<Timestamp>| {
+        suite.app.execute_contract(
+            suite.owner.clone(),
+            contract.clone(),
+            &CosmosMsg::Custom(KujiraMsg::Auth(AuthMsg::CreateVestingAccount {
+                to_address: to_address.clone(),
+                amount: vec![coin(100, "ukuji")],
+                end_time,
+                delayed: None,
+            })),
+            &[],
+        )
+    };
+    create(&carol, None).unwrap_err();
+    create(&carol, Some(start)).unwrap_err();
+    create(&bob, Some(start.plus_seconds(10))).unwrap_err();
+    create(&carol, Some(start.plus_seconds(10))).unwrap();
+}
@@ -1,8 +1,12 @@
 use cosmwasm_schema::cw_serde;
 
-use cosmwasm_std::Uint128;
+use cosmwasm_std::{
+    to_json_binary, Addr, BankMsg, Binary, CosmosMsg, QuerierWrapper, StdError, StdResult, Uint128,
+    WasmMsg,
+};
+use cw20::{BalanceResponse, Cw20ExecuteMsg, Cw20QueryMsg};
 
-use crate::denom::Denom;
+use crate::{denom::Denom, query::KujiraQuery};
 
 #[cw_serde]
 pub struct Asset {
@@ -10,7 +14,181 @@ pub struct Asset {
     pub amount: Uint128,
 }
 
+impl Asset {
+    /// Transfers this asset to `recipient`
+    pub fn transfer_msg<M>(&self, recipient: &Addr) -> StdResult<CosmosMsg<M>> {
+        Ok(match &self.info {
+            AssetInfo::NativeToken { denom } => CosmosMsg::Bank(BankMsg::Send {
+                to_address: recipient.to_string(),
+                amount: denom.coins(&self.amount),
+            }),
+            AssetInfo::Token { contract_addr } => CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: contract_addr.to_string(),
+                msg: to_json_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: recipient.to_string(),
+                    amount: self.amount,
+                })?,
+                funds: vec![],
+            }),
+        })
+    }
+
+    /// Sends this asset to `contract`, executing `msg`.
+    /// Native tokens are attached as funds to `msg`, and CW20 tokens use
+    /// `Cw20ExecuteMsg::Send`, so `msg` is delivered wrapped in a `Receive`
+    pub fn send_msg<M>(&self, contract: &Addr, msg: Binary) -> StdResult<CosmosMsg<M>> {
+        Ok(match &self.info {
+            AssetInfo::NativeToken { denom } => CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: contract.to_string(),
+                msg,
+                funds: denom.coins(&self.amount),
+            }),
+            AssetInfo::Token { contract_addr } => CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: contract_addr.to_string(),
+                msg: to_json_binary(&Cw20ExecuteMsg::Send {
+                    contract: contract.to_string(),
+                    amount: self.amount,
+                    msg,
+                })?,
+                funds: vec![],
+            }),
+        })
+    }
+}
+
 #[cw_serde]
 pub enum AssetInfo {
     NativeToken { denom: Denom },
+    Token { contract_addr: Addr },
+}
+
+impl AssetInfo {
+    pub fn query_balance(&self, q: QuerierWrapper<KujiraQuery>, addr: &Addr) -> StdResult<Uint128> {
+        match self {
+            AssetInfo::NativeToken { denom } => Ok(denom.query_balance(q, addr)?.amount),
+            AssetInfo::Token { contract_addr } => {
+                let res: BalanceResponse = q.query_wasm_smart(
+                    contract_addr,
+                    &Cw20QueryMsg::Balance {
+                        address: addr.to_string(),
+                    },
+                )?;
+                Ok(res.balance)
+            }
+        }
+    }
+}
+
+impl From<Denom> for AssetInfo {
+    fn from(denom: Denom) -> Self {
+        AssetInfo::NativeToken { denom }
+    }
+}
+
+impl TryFrom<AssetInfo> for Denom {
+    type Error = StdError;
+
+    fn try_from(info: AssetInfo) -> StdResult<Self> {
+        match info {
+            AssetInfo::NativeToken { denom } => Ok(denom),
+            AssetInfo::Token { contract_addr } => Err(StdError::generic_err(format!(
+                "CW20 token {contract_addr} has no native denom"
+            ))),
+        }
+    }
+}
+
+impl From<cw20::Denom> for AssetInfo {
+    fn from(denom: cw20::Denom) -> Self {
+        match denom {
+            cw20::Denom::Native(denom) => AssetInfo::NativeToken {
+                denom: denom.into(),
+            },
+            cw20::Denom::Cw20(contract_addr) => AssetInfo::Token { contract_addr },
+        }
+    }
+}
+
+impl From<AssetInfo> for cw20::Denom {
+    fn from(info: AssetInfo) -> Self {
+        match info {
+            AssetInfo::NativeToken { denom } => cw20::Denom::Native(denom.to_string()),
+            AssetInfo::Token { contract_addr } => cw20::Denom::Cw20(contract_addr),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::{
+        coins, from_json, to_json_binary, Addr, BankMsg, CosmosMsg, Empty, Uint128, WasmMsg,
+    };
+    use cw20::Cw20ExecuteMsg;
+
+    use super::{Asset, AssetInfo};
+    use crate::denom::Denom;
+
+    #[test]
+    fn conversions() {
+        let native = cw20::Denom::Native("ukuji".to_string());
+        let token = cw20::Denom::Cw20(Addr::unchecked("token"));
+        for denom in [native, token.clone()] {
+            assert_eq!(cw20::Denom::from(AssetInfo::from(denom.clone())), denom);
+        }
+        assert_eq!(
+            Denom::try_from(AssetInfo::from(Denom::from("ukuji"))).unwrap(),
+            Denom::from("ukuji")
+        );
+        assert!(Denom::try_from(AssetInfo::from(token.clone())).is_err());
+        assert_eq!(
+            Denom::try_from_cw20(cw20::Denom::Native("ukuji".to_string())).unwrap(),
+            Denom::from("ukuji")
+        );
+        assert!(Denom::try_from_cw20(token).is_err());
+    }
+
+    #[test]
+    fn messages() {
+        let recipient = Addr::unchecked("recipient");
+        let native = Asset {
+            info: AssetInfo::NativeToken {
+                denom: Denom::from("ukuji"),
+            },
+            amount: Uint128::from(100u128),
+        };
+        assert_eq!(
+            native.transfer_msg::<Empty>(&recipient).unwrap(),
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: recipient.to_string(),
+                amount: coins(100, "ukuji"),
+            })
+        );
+
+        let token = Asset {
+            info: AssetInfo::Token {
+                contract_addr: Addr::unchecked("token"),
+            },
+            amount: Uint128::from(100u128),
+        };
+        let inner = to_json_binary(&"hook").unwrap();
+        match token.send_msg::<Empty>(&recipient, inner.clone()).unwrap() {
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr,
+                msg,
+                funds,
+            }) => {
+                assert_eq!(contract_addr, "token");
+                assert!(funds.is_empty());
+                assert_eq!(
+                    from_json::<Cw20ExecuteMsg>(msg).unwrap(),
+                    Cw20ExecuteMsg::Send {
+                        contract: recipient.to_string(),
+                        amount: Uint128::from(100u128),
+                        msg: inner,
+                    }
+                );
+            }
+            msg => panic!("unexpected {msg:?}"),
+        }
+    }
 }
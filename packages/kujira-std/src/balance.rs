@@ -0,0 +1,195 @@
+//! A list of coins with checked, per-denom arithmetic
+
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Coin, OverflowError, OverflowOperation, Uint128};
+
+use crate::denom::Denom;
+
+/// At most one coin per denom, sorted by denom and without zero amounts.
+/// Serializes as a plain list of coins, and is normalized when deserialized
+#[cw_serde]
+#[derive(Default)]
+#[serde(try_from = "Vec<Coin>", into = "Vec<Coin>")]
+pub struct Balance(Vec<Coin>);
+
+impl Balance {
+    /// Merges duplicate denoms and drops zero amounts
+    pub fn new(coins: Vec<Coin>) -> Result<Self, OverflowError> {
+        let mut balance = Self::default();
+        for coin in coins {
+            balance.add(&coin)?;
+        }
+        Ok(balance)
+    }
+
+    pub fn coins(&self) -> &[Coin] {
+        &self.0
+    }
+
+    pub fn into_vec(self) -> Vec<Coin> {
+        self.0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Coin> {
+        self.0.iter()
+    }
+
+    pub fn denoms(&self) -> impl Iterator<Item = Denom> + '_ {
+        self.0.iter().map(|c| Denom::from(c.denom.clone()))
+    }
+
+    pub fn amount_of(&self, denom: &Denom) -> Uint128 {
+        self.position(denom.as_ref())
+            .map(|idx| self.0[idx].amount)
+            .unwrap_or_default()
+    }
+
+    pub fn add(&mut self, coin: &Coin) -> Result<(), OverflowError> {
+        if coin.amount.is_zero() {
+            return Ok(());
+        }
+        match self.position(&coin.denom) {
+            Some(idx) => {
+                self.0[idx].amount = self.0[idx].amount.checked_add(coin.amount)?;
+            }
+            None => {
+                let idx = self.0.partition_point(|c| c.denom < coin.denom);
+                self.0.insert(idx, coin.clone());
+            }
+        }
+        Ok(())
+    }
+
+    /// Fails if there isn't enough of `coin.denom`, leaving the balance unchanged
+    pub fn sub(&mut self, coin: &Coin) -> Result<(), OverflowError> {
+        if coin.amount.is_zero() {
+            return Ok(());
+        }
+        let idx = self
+            .position(&coin.denom)
+            .ok_or_else(|| OverflowError::new(OverflowOperation::Sub))?;
+        self.0[idx].amount = self.0[idx].amount.checked_sub(coin.amount)?;
+        if self.0[idx].amount.is_zero() {
+            self.0.remove(idx);
+        }
+        Ok(())
+    }
+
+    /// Adds every coin in `other`. Nothing is added if any denom overflows
+    pub fn merge(&mut self, other: &Balance) -> Result<(), OverflowError> {
+        let mut merged = self.clone();
+        for coin in other.iter() {
+            merged.add(coin)?;
+        }
+        *self = merged;
+        Ok(())
+    }
+
+    /// Subtracts every coin in `other`. Nothing is subtracted if any denom is insufficient
+    pub fn checked_sub_balance(&mut self, other: &Balance) -> Result<(), OverflowError> {
+        let mut remaining = self.clone();
+        for coin in other.iter() {
+            remaining.sub(coin)?;
+        }
+        *self = remaining;
+        Ok(())
+    }
+
+    fn position(&self, denom: &str) -> Option<usize> {
+        self.0.iter().position(|c| c.denom == denom)
+    }
+}
+
+impl TryFrom<Vec<Coin>> for Balance {
+    type Error = OverflowError;
+
+    fn try_from(coins: Vec<Coin>) -> Result<Self, Self::Error> {
+        Self::new(coins)
+    }
+}
+
+impl From<Balance> for Vec<Coin> {
+    fn from(balance: Balance) -> Self {
+        balance.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::{coin, Uint128};
+
+    use super::Balance;
+    use crate::denom::Denom;
+
+    #[test]
+    fn normalizes() {
+        let balance = Balance::new(vec![
+            coin(3, "uusk"),
+            coin(0, "uatom"),
+            coin(1, "ukuji"),
+            coin(2, "uusk"),
+        ])
+        .unwrap();
+        assert_eq!(balance.coins(), &[coin(1, "ukuji"), coin(5, "uusk")]);
+        assert_eq!(
+            balance.amount_of(&Denom::from("uusk")),
+            Uint128::from(5u128)
+        );
+        assert_eq!(balance.amount_of(&Denom::from("uatom")), Uint128::zero());
+
+        let raw: Balance = serde_json::from_str(
+            r#"[{"denom":"uusk","amount":"5"},{"denom":"uusk","amount":"7"},{"denom":"ukuji","amount":"0"}]"#,
+        )
+        .unwrap();
+        assert_eq!(raw.coins(), &[coin(12, "uusk")]);
+        assert_eq!(raw.amount_of(&Denom::from("uusk")), Uint128::from(12u128));
+        assert_eq!(
+            serde_json::to_string(&raw).unwrap(),
+            r#"[{"denom":"uusk","amount":"12"}]"#
+        );
+
+        let overflow = format!(
+            r#"[{{"denom":"uusk","amount":"{}"}},{{"denom":"uusk","amount":"1"}}]"#,
+            u128::MAX
+        );
+        assert!(serde_json::from_str::<Balance>(&overflow).is_err());
+    }
+
+    #[test]
+    fn arithmetic() {
+        let mut balance = Balance::new(vec![coin(10, "ukuji"), coin(5, "uusk")]).unwrap();
+        balance.sub(&coin(5, "uusk")).unwrap();
+        assert_eq!(balance.coins(), &[coin(10, "ukuji")]);
+        assert!(balance.sub(&coin(1, "uusk")).is_err());
+        assert!(balance.sub(&coin(11, "ukuji")).is_err());
+
+        balance.add(&coin(1, "uatom")).unwrap();
+        assert_eq!(balance.coins(), &[coin(1, "uatom"), coin(10, "ukuji")]);
+        assert!(balance.add(&coin(u128::MAX, "ukuji")).is_err());
+        assert_eq!(
+            balance.amount_of(&Denom::from("ukuji")),
+            Uint128::from(10u128)
+        );
+    }
+
+    #[test]
+    fn merge_and_sub_are_atomic() {
+        let mut balance = Balance::new(vec![coin(10, "ukuji"), coin(5, "uusk")]).unwrap();
+        let other = Balance::new(vec![coin(1, "ukuji"), coin(6, "uusk")]).unwrap();
+        assert!(balance.checked_sub_balance(&other).is_err());
+        assert_eq!(balance.coins(), &[coin(10, "ukuji"), coin(5, "uusk")]);
+
+        balance.merge(&other).unwrap();
+        assert_eq!(balance.coins(), &[coin(11, "ukuji"), coin(11, "uusk")]);
+        balance.checked_sub_balance(&other).unwrap();
+        assert_eq!(balance.coins(), &[coin(10, "ukuji"), coin(5, "uusk")]);
+
+        let max = Balance::new(vec![coin(1, "uatom"), coin(u128::MAX, "uusk")]).unwrap();
+        assert!(balance.merge(&max).is_err());
+        assert_eq!(balance.coins(), &[coin(10, "ukuji"), coin(5, "uusk")]);
+    }
+}
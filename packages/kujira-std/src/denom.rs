@@ -2,7 +2,11 @@
 use std::fmt::Display;
 
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{coin, coins, Addr, BankMsg, Coin, CosmosMsg, QuerierWrapper, StdResult};
+use cosmwasm_std::{
+    coin, coins, Addr, BankMsg, Coin, CosmosMsg, QuerierWrapper, StdError, StdResult,
+};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
 
 use crate::query::KujiraQuery;
 
@@ -39,16 +43,123 @@ impl Denom {
         q.query_balance(addr.clone(), self.0.to_string())
     }
 
+    /// Parses and validates `value`, see [Denom::kind]
+    pub fn parse<T: Into<String>>(value: T) -> Result<Self, DenomError> {
+        let denom = Self(value.into());
+        denom.kind()?;
+        Ok(denom)
+    }
+
+    /// The IBC denom of the token at the end of `trace`, eg `transfer/channel-0/uatom`
+    pub fn ibc<T: AsRef<str>>(trace: T) -> Self {
+        let hash = Sha256::digest(trace.as_ref().as_bytes());
+        Self(format!("{IBC_PREFIX}{}", hex::encode_upper(hash)))
+    }
+
+    /// Classifies this denom, validating it against the Cosmos SDK denom rules
+    /// and the structure of its kind
+    pub fn kind(&self) -> Result<DenomKind, DenomError> {
+        validate(&self.0)?;
+        if let Some(rest) = self.0.strip_prefix(FACTORY_PREFIX) {
+            let (creator, subdenom) = rest.split_once('/').ok_or(DenomError::InvalidFactory {})?;
+            if creator.is_empty() || subdenom.is_empty() {
+                return Err(DenomError::InvalidFactory {});
+            }
+            return Ok(DenomKind::Factory {
+                creator: creator.to_string(),
+                subdenom: subdenom.to_string(),
+            });
+        }
+        if let Some(hash) = self.0.strip_prefix(IBC_PREFIX) {
+            if hash.len() != 64 || !hash.chars().all(|c| matches!(c, '0'..='9' | 'A'..='F')) {
+                return Err(DenomError::InvalidIbc {});
+            }
+            return Ok(DenomKind::Ibc {
+                hash: hash.to_string(),
+            });
+        }
+        Ok(DenomKind::Native {})
+    }
+
+    /// The creator of a Token Factory denom
+    pub fn factory_creator(&self) -> Option<&str> {
+        self.factory_parts().map(|(creator, _)| creator)
+    }
+
+    /// The subdenom of a Token Factory denom
+    pub fn factory_subdenom(&self) -> Option<&str> {
+        self.factory_parts().map(|(_, subdenom)| subdenom)
+    }
+
+    fn factory_parts(&self) -> Option<(&str, &str)> {
+        self.0
+            .strip_prefix(FACTORY_PREFIX)
+            .and_then(|rest| rest.split_once('/'))
+            .filter(|(creator, subdenom)| !creator.is_empty() && !subdenom.is_empty())
+    }
+
     pub fn as_bytes(&self) -> &[u8] {
         self.0.as_bytes()
     }
 
+    #[deprecated(note = "Panics for CW20 tokens. Use `Denom::try_from_cw20` instead")]
     pub fn from_cw20(value: cw20::Denom) -> Self {
         match value {
             cw20::Denom::Native(x) => Self::from(x),
             cw20::Denom::Cw20(_) => panic!("CW20 Unsupported"),
         }
     }
+
+    /// Fails for CW20 tokens, which have no native denom. See [AssetInfo](crate::AssetInfo)
+    /// for a type that holds either.
+    /// `TryFrom<cw20::Denom>` would conflict with the blanket `From<T: Into<String>>`
+    pub fn try_from_cw20(value: cw20::Denom) -> StdResult<Self> {
+        match value {
+            cw20::Denom::Native(x) => Ok(Self::from(x)),
+            cw20::Denom::Cw20(addr) => Err(StdError::generic_err(format!(
+                "CW20 token {addr} has no native denom"
+            ))),
+        }
+    }
+}
+
+const FACTORY_PREFIX: &str = "factory/";
+const IBC_PREFIX: &str = "ibc/";
+
+#[cw_serde]
+pub enum DenomKind {
+    Native {},
+    Factory { creator: String, subdenom: String },
+    Ibc { hash: String },
+}
+
+#[derive(Error, Debug, PartialEq)]
+pub enum DenomError {
+    #[error("Denom must be 3 to 128 characters")]
+    InvalidLength {},
+
+    #[error("Denom must start with a letter and contain only letters, digits and /:._-")]
+    InvalidCharacters {},
+
+    #[error("Factory denoms must be factory/{{creator}}/{{subdenom}}")]
+    InvalidFactory {},
+
+    #[error("IBC denoms must be ibc/{{hash}}, with an uppercase hex SHA-256 hash")]
+    InvalidIbc {},
+}
+
+/// Matches the Cosmos SDK's `[a-zA-Z][a-zA-Z0-9/:._-]{2,127}`
+fn validate(denom: &str) -> Result<(), DenomError> {
+    if !(3..=128).contains(&denom.len()) {
+        return Err(DenomError::InvalidLength {});
+    }
+    let mut chars = denom.chars();
+    let valid = chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '/' | ':' | '.' | '_' | '-'));
+    if !valid {
+        return Err(DenomError::InvalidCharacters {});
+    }
+    Ok(())
 }
 
 impl Display for Denom {
@@ -66,8 +177,69 @@ where
     }
 }
 
+impl From<Denom> for cw20::Denom {
+    fn from(denom: Denom) -> Self {
+        cw20::Denom::Native(denom.0)
+    }
+}
+
 impl AsRef<str> for Denom {
     fn as_ref(&self) -> &str {
         self.0.as_ref()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{Denom, DenomError, DenomKind};
+
+    #[test]
+    fn kinds() {
+        assert_eq!(Denom::from("ukuji").kind(), Ok(DenomKind::Native {}));
+        assert_eq!(
+            Denom::from("factory/kujira1abc/ulp/x").kind(),
+            Ok(DenomKind::Factory {
+                creator: "kujira1abc".to_string(),
+                subdenom: "ulp/x".to_string()
+            })
+        );
+        let ibc = Denom::ibc("transfer/channel-0/uatom");
+        assert_eq!(
+            ibc.to_string(),
+            "ibc/27394FB092D2ECCD56123C74F36E4C1F926001CEADA9CA97EA622B25F41E5EB2"
+        );
+        assert!(matches!(ibc.kind(), Ok(DenomKind::Ibc { .. })));
+    }
+
+    #[test]
+    fn invalid() {
+        assert_eq!(Denom::parse("uk"), Err(DenomError::InvalidLength {}));
+        assert_eq!(
+            Denom::parse("a".repeat(129)),
+            Err(DenomError::InvalidLength {})
+        );
+        assert_eq!(Denom::parse("1kuji"), Err(DenomError::InvalidCharacters {}));
+        assert_eq!(
+            Denom::parse("u kuji"),
+            Err(DenomError::InvalidCharacters {})
+        );
+        assert_eq!(
+            Denom::parse("factory/abc"),
+            Err(DenomError::InvalidFactory {})
+        );
+        assert_eq!(
+            Denom::parse("factory//ulp"),
+            Err(DenomError::InvalidFactory {})
+        );
+        assert_eq!(Denom::parse("ibc/27394fb0"), Err(DenomError::InvalidIbc {}));
+        assert!(Denom::parse("ibcx").is_ok());
+    }
+
+    #[test]
+    fn factory_accessors() {
+        let denom = Denom::from("factory/kujira1abc/ulp");
+        assert_eq!(denom.factory_creator(), Some("kujira1abc"));
+        assert_eq!(denom.factory_subdenom(), Some("ulp"));
+        assert_eq!(Denom::from("ukuji").factory_creator(), None);
+    }
+}
@@ -0,0 +1,198 @@
+//! Extraction and validation of the funds sent with a message
+
+use cosmwasm_std::{Coin, StdError, Uint128};
+use thiserror::Error;
+
+use crate::denom::Denom;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum FundsError {
+    #[error("No funds sent")]
+    NoFunds {},
+
+    #[error("Missing funds: {denom}")]
+    Missing { denom: Denom },
+
+    #[error("Unexpected funds: {denom}")]
+    Unexpected { denom: Denom },
+
+    #[error("Duplicate funds: {denom}")]
+    Duplicate { denom: Denom },
+
+    #[error("Expected a single denom, received {count}")]
+    Multiple { count: usize },
+}
+
+impl From<FundsError> for StdError {
+    fn from(err: FundsError) -> Self {
+        StdError::generic_err(err.to_string())
+    }
+}
+
+/// The funds sent with a message, usually `info.funds`. Zero amounts are ignored
+#[derive(Clone, Debug, PartialEq)]
+pub struct Funds(Vec<Coin>);
+
+impl Funds {
+    pub fn new(funds: Vec<Coin>) -> Result<Self, FundsError> {
+        let mut coins: Vec<Coin> = vec![];
+        for coin in funds.into_iter().filter(|c| !c.amount.is_zero()) {
+            if coins.iter().any(|c| c.denom == coin.denom) {
+                return Err(FundsError::Duplicate {
+                    denom: coin.denom.into(),
+                });
+            }
+            coins.push(coin);
+        }
+        Ok(Self(coins))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// The amount of `denom`, which must be the only denom sent
+    pub fn exactly(&self, denom: &Denom) -> Result<Uint128, FundsError> {
+        self.only(std::slice::from_ref(denom))?;
+        self.required(denom)
+    }
+
+    /// The single coin sent, which must be one of `denoms`
+    pub fn one_of(&self, denoms: &[Denom]) -> Result<(Denom, Uint128), FundsError> {
+        self.only(denoms)?;
+        match self.0.as_slice() {
+            [] => Err(FundsError::NoFunds {}),
+            [coin] => Ok((coin.denom.clone().into(), coin.amount)),
+            coins => Err(FundsError::Multiple { count: coins.len() }),
+        }
+    }
+
+    /// The amounts of both `denoms`, in the same order. Both must be sent, and nothing else
+    pub fn pair(&self, denoms: &[Denom; 2]) -> Result<[Uint128; 2], FundsError> {
+        self.only(denoms)?;
+        Ok([self.required(&denoms[0])?, self.required(&denoms[1])?])
+    }
+
+    /// The amount of `denom`, which must have been sent
+    pub fn required(&self, denom: &Denom) -> Result<Uint128, FundsError> {
+        self.find(denom).ok_or_else(|| FundsError::Missing {
+            denom: denom.clone(),
+        })
+    }
+
+    /// The amount of `denom`, or zero if it wasn't sent
+    pub fn optional(&self, denom: &Denom) -> Uint128 {
+        self.find(denom).unwrap_or_default()
+    }
+
+    /// Fails if anything other than `denoms` was sent
+    pub fn only(&self, denoms: &[Denom]) -> Result<(), FundsError> {
+        match self
+            .0
+            .iter()
+            .find(|c| !denoms.iter().any(|d| d.as_ref() == c.denom))
+        {
+            Some(coin) => Err(FundsError::Unexpected {
+                denom: coin.denom.clone().into(),
+            }),
+            None => Ok(()),
+        }
+    }
+
+    fn find(&self, denom: &Denom) -> Option<Uint128> {
+        self.0
+            .iter()
+            .find(|c| denom.as_ref() == c.denom)
+            .map(|c| c.amount)
+    }
+}
+
+impl From<Funds> for Vec<Coin> {
+    fn from(funds: Funds) -> Self {
+        funds.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::{coin, coins, Uint128};
+
+    use super::{Funds, FundsError};
+    use crate::denom::Denom;
+
+    fn denoms() -> [Denom; 2] {
+        [Denom::from("ukuji"), Denom::from("uusk")]
+    }
+
+    #[test]
+    fn exactly() {
+        let [kuji, usk] = denoms();
+        let funds = Funds::new(coins(100, "ukuji")).unwrap();
+        assert_eq!(funds.exactly(&kuji), Ok(Uint128::from(100u128)));
+        assert_eq!(
+            funds.exactly(&usk),
+            Err(FundsError::Unexpected { denom: kuji })
+        );
+
+        let funds = Funds::new(vec![]).unwrap();
+        assert_eq!(funds.exactly(&usk), Err(FundsError::Missing { denom: usk }));
+    }
+
+    #[test]
+    fn one_of() {
+        let [kuji, usk] = denoms();
+        let both = Funds::new(vec![coin(1, "ukuji"), coin(2, "uusk")]).unwrap();
+        assert_eq!(
+            both.one_of(&denoms()),
+            Err(FundsError::Multiple { count: 2 })
+        );
+        let single = Funds::new(coins(2, "uusk")).unwrap();
+        assert_eq!(
+            single.one_of(&denoms()),
+            Ok((usk.clone(), Uint128::from(2u128)))
+        );
+        assert_eq!(
+            single.one_of(&[kuji]),
+            Err(FundsError::Unexpected { denom: usk })
+        );
+        assert_eq!(
+            Funds::new(vec![]).unwrap().one_of(&denoms()),
+            Err(FundsError::NoFunds {})
+        );
+    }
+
+    #[test]
+    fn pair() {
+        let [kuji, usk] = denoms();
+        // Sorted by denom, but returned in config order
+        let funds = Funds::new(vec![coin(1, "uatom"), coin(2, "ukuji"), coin(3, "uusk")]).unwrap();
+        assert_eq!(
+            funds.pair(&[usk.clone(), kuji.clone()]),
+            Err(FundsError::Unexpected {
+                denom: Denom::from("uatom")
+            })
+        );
+        let funds = Funds::new(vec![coin(2, "ukuji"), coin(3, "uusk")]).unwrap();
+        assert_eq!(
+            funds.pair(&[usk.clone(), kuji.clone()]),
+            Ok([Uint128::from(3u128), Uint128::from(2u128)])
+        );
+        let funds = Funds::new(vec![coin(2, "ukuji"), coin(0, "uusk")]).unwrap();
+        assert_eq!(
+            funds.pair(&[kuji.clone(), usk.clone()]),
+            Err(FundsError::Missing { denom: usk.clone() })
+        );
+        assert_eq!(funds.optional(&usk), Uint128::zero());
+        assert_eq!(funds.only(&[kuji]), Ok(()));
+    }
+
+    #[test]
+    fn duplicates() {
+        assert_eq!(
+            Funds::new(vec![coin(1, "ukuji"), coin(2, "ukuji")]),
+            Err(FundsError::Duplicate {
+                denom: Denom::from("ukuji")
+            })
+        );
+    }
+}
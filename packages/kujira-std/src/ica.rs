@@ -69,8 +69,10 @@ pub enum IcaMsg {
 }
 
 #[cw_serde]
+#[derive(Default)]
 #[serde(untagged)]
 pub enum IcaRegisterVersion {
+    #[default]
     #[serde(serialize_with = "serialize_empty_string")]
     Default,
     #[serde(serialize_with = "serialize_ics27")]
@@ -79,12 +81,6 @@ pub enum IcaRegisterVersion {
     Ics29(Ics29MetadataInit),
 }
 
-impl Default for IcaRegisterVersion {
-    fn default() -> Self {
-        Self::Default
-    }
-}
-
 fn serialize_empty_string<S>(serializer: S) -> Result<S::Ok, S::Error>
 where
     S: Serializer,
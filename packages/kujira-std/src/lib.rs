@@ -1,6 +1,8 @@
 mod asset;
+mod balance;
 mod callback;
 mod denom;
+mod funds;
 mod ica;
 mod merkle;
 mod msg;
@@ -13,18 +15,27 @@ mod utils;
 
 pub use {
     asset::{Asset, AssetInfo},
+    balance::Balance,
     callback::{CallbackData, CallbackMsg},
-    denom::Denom,
+    denom::{Denom, DenomError, DenomKind},
+    funds::{Funds, FundsError},
     ica::*,
-    merkle::{Error as MerkleError, Merkle, Proof},
+    merkle::{
+        Error as MerkleError, HashMode as MerkleHashMode, Leaf as MerkleLeaf, Merkle, MerkleTree,
+        MultiProof, MultiProofFlag, Proof,
+    },
     msg::{AuthMsg, BatchMsg, DenomMsg, KujiraMsg},
-    precision::{Precise, Precision},
-    price::{HumanPrice, NormalizedPrice},
+    precision::{LotSize, Precise, Precision, PrecisionError, Rounding, TickSize},
+    price::{HumanPrice, NormalizedPrice, PairPrice},
     querier::KujiraQuerier,
     query::{
-        BankQuery, DenomAdminResponse, DenomQuery, ExchangeRateResponse, FullDenomResponse,
-        KujiraQuery, OracleQuery, SupplyResponse,
+        AccountAddressResponse, BankQuery, DenomAdminResponse, DenomQuery, ExchangeRateResponse,
+        FullDenomResponse, IbcVerifyQuery, IbcVerifyResponse, IcaQuery, KujiraQuery, OracleQuery,
+        SupplyResponse,
     },
-    schedule::{Release, Schedule},
-    utils::{amount, fee_address},
+    schedule::{Checkpoint, Release, Samples, Schedule, ScheduleError},
+    utils::fee_address,
 };
+
+#[allow(deprecated)]
+pub use utils::amount;
@@ -1,17 +1,102 @@
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::StdError;
+use cosmwasm_std::{Addr, Coin, StdError, Uint128};
 use hex::FromHexError;
 use sha2::Digest;
-use std::convert::TryInto;
+use std::{collections::VecDeque, convert::TryInto};
 use thiserror::Error;
 
 #[cw_serde]
 pub struct Merkle {
     root: String,
+    /// Omitted when plain, so roots keep the wire and storage format of earlier versions
+    #[serde(default, skip_serializing_if = "HashMode::is_plain")]
+    mode: HashMode,
 }
 
 pub type Proof = Vec<String>;
 
+/// Proves many leaves against one root, sharing the siblings they have in common
+#[cw_serde]
+pub struct MultiProof {
+    /// Sibling hashes that can't be computed from the leaves, in the order they're used
+    pub proof: Proof,
+    pub flags: Vec<MultiProofFlag>,
+}
+
+/// One hashing step of a [MultiProof]. Each step takes the next node from a queue that
+/// starts with the leaves, and appends its result to the back
+#[cw_serde]
+#[derive(Copy)]
+pub enum MultiProofFlag {
+    /// The sibling is the next node in the queue
+    Known,
+    /// The sibling is the next hash in `proof`
+    Proof,
+    /// The node is the last of an odd layer, and is promoted unchanged
+    Promote,
+}
+
+/// How leaves and inner nodes are hashed
+#[cw_serde]
+#[derive(Copy, Default)]
+pub enum HashMode {
+    /// `sha256(leaf)` and `sha256(sorted pair)`
+    #[default]
+    Plain,
+    /// `sha256(0x00 || leaf)` and `sha256(0x01 || sorted pair)`, so that a leaf
+    /// can't be passed off as an inner node
+    DomainSeparated,
+}
+
+impl HashMode {
+    fn is_plain(&self) -> bool {
+        matches!(self, HashMode::Plain)
+    }
+}
+
+/// A value with a canonical leaf encoding
+pub trait Leaf {
+    fn encode(&self) -> String;
+}
+
+impl Leaf for str {
+    fn encode(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl Leaf for String {
+    fn encode(&self) -> String {
+        self.clone()
+    }
+}
+
+impl<T: Leaf + ?Sized> Leaf for &T {
+    fn encode(&self) -> String {
+        (**self).encode()
+    }
+}
+
+/// `{addr},{amount}`
+impl Leaf for (Addr, Uint128) {
+    fn encode(&self) -> String {
+        format!("{},{}", self.0, self.1)
+    }
+}
+
+/// `{addr},{amount}{denom},...`, with coins sorted by denom
+impl Leaf for (Addr, Vec<Coin>) {
+    fn encode(&self) -> String {
+        let mut coins = self.1.clone();
+        coins.sort_by(|a, b| a.denom.cmp(&b.denom));
+        let mut encoded = self.0.to_string();
+        for coin in coins {
+            encoded.push_str(&format!(",{coin}"));
+        }
+        encoded
+    }
+}
+
 #[derive(Error, Debug, PartialEq)]
 pub enum Error {
     #[error("{0}")]
@@ -25,33 +110,76 @@ pub enum Error {
 
     #[error("Verification failed")]
     VerificationFailed {},
+
+    #[error("Empty tree")]
+    EmptyTree {},
 }
 
 impl Merkle {
     pub fn new(root: String) -> Result<Self, Error> {
+        Self::with_mode(root, HashMode::Plain)
+    }
+
+    pub fn with_mode(root: String, mode: HashMode) -> Result<Self, Error> {
         let mut root_buf: [u8; 32] = [0; 32];
         hex::decode_to_slice(&root, &mut root_buf)?;
 
-        Ok(Self { root })
+        Ok(Self { root, mode })
     }
 
     pub fn verify(&self, proof: Proof, input: String) -> Result<(), Error> {
-        let hash = sha2::Sha256::digest(input.as_bytes())
-            .as_slice()
-            .try_into()
-            .map_err(|_| Error::WrongLength {})?;
+        self.verify_leaf(proof, &input)
+    }
+
+    pub fn verify_leaf<T: Leaf + ?Sized>(&self, proof: Proof, leaf: &T) -> Result<(), Error> {
+        let hash = hash_leaf(self.mode, leaf)?;
 
         let hash = proof.into_iter().try_fold(hash, |hash, p| {
             let mut proof_buf = [0; 32];
             hex::decode_to_slice(p, &mut proof_buf)?;
-            let mut hashes = [hash, proof_buf];
-            hashes.sort_unstable();
-            sha2::Sha256::digest(hashes.concat())
-                .as_slice()
-                .try_into()
-                .map_err(|_| Error::WrongLength {})
+            hash_pair(self.mode, hash, proof_buf)
         })?;
 
+        self.verify_root(hash)
+    }
+
+    /// Verifies `leaves` with a proof from [MerkleTree::multi_proof]. Leaves must be
+    /// given in the order they appear in the tree
+    pub fn verify_multi<T: Leaf>(&self, proof: MultiProof, leaves: &[T]) -> Result<(), Error> {
+        if leaves.is_empty() {
+            return Err(Error::VerificationFailed {});
+        }
+        let mut queue = leaves
+            .iter()
+            .map(|leaf| hash_leaf(self.mode, leaf))
+            .collect::<Result<VecDeque<_>, Error>>()?;
+        let mut siblings = proof.proof.into_iter();
+
+        for flag in proof.flags {
+            let node = queue.pop_front().ok_or(Error::VerificationFailed {})?;
+            let next = match flag {
+                MultiProofFlag::Known => {
+                    let sibling = queue.pop_front().ok_or(Error::VerificationFailed {})?;
+                    hash_pair(self.mode, node, sibling)?
+                }
+                MultiProofFlag::Proof => {
+                    let sibling = siblings.next().ok_or(Error::VerificationFailed {})?;
+                    let mut proof_buf = [0; 32];
+                    hex::decode_to_slice(sibling, &mut proof_buf)?;
+                    hash_pair(self.mode, node, proof_buf)?
+                }
+                MultiProofFlag::Promote => node,
+            };
+            queue.push_back(next);
+        }
+
+        if queue.len() != 1 || siblings.next().is_some() {
+            return Err(Error::VerificationFailed {});
+        }
+        self.verify_root(queue[0])
+    }
+
+    fn verify_root(&self, hash: [u8; 32]) -> Result<(), Error> {
         let mut root_buf: [u8; 32] = [0; 32];
         hex::decode_to_slice(self.root.clone(), &mut root_buf)?;
         if root_buf != hash {
@@ -60,3 +188,277 @@ impl Merkle {
         Ok(())
     }
 }
+
+fn hash_leaf<T: Leaf + ?Sized>(mode: HashMode, leaf: &T) -> Result<[u8; 32], Error> {
+    let mut hasher = sha2::Sha256::new();
+    if mode == HashMode::DomainSeparated {
+        hasher.update([0x00]);
+    }
+    hasher.update(leaf.encode().as_bytes());
+    hasher
+        .finalize()
+        .as_slice()
+        .try_into()
+        .map_err(|_| Error::WrongLength {})
+}
+
+fn hash_pair(mode: HashMode, a: [u8; 32], b: [u8; 32]) -> Result<[u8; 32], Error> {
+    let mut hashes = [a, b];
+    hashes.sort_unstable();
+    let mut hasher = sha2::Sha256::new();
+    if mode == HashMode::DomainSeparated {
+        hasher.update([0x01]);
+    }
+    hasher.update(hashes.concat());
+    hasher
+        .finalize()
+        .as_slice()
+        .try_into()
+        .map_err(|_| Error::WrongLength {})
+}
+
+/// Builds the tree that [Merkle::verify] checks proofs against. Leaves are hashed
+/// in the order given, and the last node of an odd layer is promoted unchanged
+#[derive(Clone, Debug, PartialEq)]
+pub struct MerkleTree {
+    layers: Vec<Vec<[u8; 32]>>,
+    mode: HashMode,
+}
+
+impl MerkleTree {
+    pub fn new<T: AsRef<str>>(leaves: &[T]) -> Result<Self, Error> {
+        let leaves: Vec<&str> = leaves.iter().map(AsRef::as_ref).collect();
+        Self::with_mode(&leaves, HashMode::Plain)
+    }
+
+    pub fn with_mode<T: Leaf>(leaves: &[T], mode: HashMode) -> Result<Self, Error> {
+        if leaves.is_empty() {
+            return Err(Error::EmptyTree {});
+        }
+        let mut layer = leaves
+            .iter()
+            .map(|leaf| hash_leaf(mode, leaf))
+            .collect::<Result<Vec<[u8; 32]>, Error>>()?;
+
+        let mut layers = vec![];
+        while layer.len() > 1 {
+            let next = layer
+                .chunks(2)
+                .map(|pair| match pair {
+                    [a, b] => hash_pair(mode, *a, *b),
+                    [a] => Ok(*a),
+                    _ => unreachable!(),
+                })
+                .collect::<Result<Vec<_>, Error>>()?;
+            layers.push(layer);
+            layer = next;
+        }
+        layers.push(layer);
+        Ok(Self { layers, mode })
+    }
+
+    pub fn root(&self) -> String {
+        hex::encode(self.layers[self.layers.len() - 1][0])
+    }
+
+    pub fn merkle(&self) -> Merkle {
+        Merkle {
+            root: self.root(),
+            mode: self.mode,
+        }
+    }
+
+    /// The proof for the leaf at `index`, or `None` if it's out of bounds
+    pub fn proof(&self, index: usize) -> Option<Proof> {
+        if index >= self.layers[0].len() {
+            return None;
+        }
+        let mut index = index;
+        let mut proof = vec![];
+        for layer in &self.layers[..self.layers.len() - 1] {
+            if let Some(sibling) = layer.get(index ^ 1) {
+                proof.push(hex::encode(sibling));
+            }
+            index /= 2;
+        }
+        Some(proof)
+    }
+
+    /// Proofs for every leaf, in the order the leaves were given
+    pub fn proofs(&self) -> Vec<Proof> {
+        (0..self.layers[0].len())
+            .filter_map(|index| self.proof(index))
+            .collect()
+    }
+
+    /// A single proof for the leaves at `indices`, or `None` if it's empty or out of bounds.
+    /// The leaves are verified in ascending index order, with duplicates removed
+    pub fn multi_proof(&self, indices: &[usize]) -> Option<MultiProof> {
+        let mut known = indices.to_vec();
+        known.sort_unstable();
+        known.dedup();
+        if known.is_empty() || known[known.len() - 1] >= self.layers[0].len() {
+            return None;
+        }
+
+        let mut proof = vec![];
+        let mut flags = vec![];
+        for layer in &self.layers[..self.layers.len() - 1] {
+            let mut next = vec![];
+            let mut i = 0;
+            while i < known.len() {
+                let index = known[i];
+                let sibling = index ^ 1;
+                if sibling >= layer.len() {
+                    flags.push(MultiProofFlag::Promote);
+                    i += 1;
+                } else if known.get(i + 1) == Some(&sibling) {
+                    flags.push(MultiProofFlag::Known);
+                    i += 2;
+                } else {
+                    flags.push(MultiProofFlag::Proof);
+                    proof.push(hex::encode(layer[sibling]));
+                    i += 1;
+                }
+                next.push(index / 2);
+            }
+            known = next;
+        }
+        Some(MultiProof { proof, flags })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::{from_json, to_json_string};
+
+    use super::*;
+
+    #[test]
+    fn plain_mode_wire_format() {
+        let root = "a".repeat(64);
+        let plain = Merkle::new(root.clone()).unwrap();
+        let json = to_json_string(&plain).unwrap();
+        assert_eq!(json, format!(r#"{{"root":"{root}"}}"#));
+        assert_eq!(from_json::<Merkle>(&json).unwrap(), plain);
+
+        let separated = Merkle::with_mode(root, HashMode::DomainSeparated).unwrap();
+        let json = to_json_string(&separated).unwrap();
+        assert!(json.contains(r#""mode":"domain_separated""#));
+        assert_eq!(from_json::<Merkle>(&json).unwrap(), separated);
+    }
+
+    #[test]
+    fn tree_proofs_verify() {
+        for n in 1..=9 {
+            let leaves: Vec<String> = (0..n).map(|i| format!("kujira1addr{i},{i}00")).collect();
+            let tree = MerkleTree::new(&leaves).unwrap();
+            let merkle = Merkle::new(tree.root()).unwrap();
+            for (leaf, proof) in leaves.iter().zip(tree.proofs()) {
+                merkle.verify(proof, leaf.clone()).unwrap();
+            }
+            assert_eq!(
+                merkle.verify(tree.proof(0).unwrap(), "kujira1other".to_string()),
+                Err(Error::VerificationFailed {})
+            );
+            assert_eq!(tree.proof(n), None);
+        }
+    }
+
+    #[test]
+    fn single_leaf() {
+        let tree = MerkleTree::new(&["leaf"]).unwrap();
+        assert_eq!(tree.root(), hex::encode(sha2::Sha256::digest(b"leaf")));
+        assert_eq!(tree.proof(0), Some(vec![]));
+        assert_eq!(MerkleTree::new::<&str>(&[]), Err(Error::EmptyTree {}));
+    }
+
+    #[test]
+    fn typed_leaves() {
+        let leaves = vec![
+            (Addr::unchecked("kujira1a"), Uint128::from(100u128)),
+            (Addr::unchecked("kujira1b"), Uint128::from(200u128)),
+            (Addr::unchecked("kujira1c"), Uint128::from(300u128)),
+        ];
+        assert_eq!(leaves[0].encode(), "kujira1a,100");
+
+        let tree = MerkleTree::with_mode(&leaves, HashMode::Plain).unwrap();
+        let merkle = tree.merkle();
+        merkle
+            .verify_leaf(tree.proof(1).unwrap(), &leaves[1])
+            .unwrap();
+        merkle
+            .verify(tree.proof(1).unwrap(), "kujira1b,200".to_string())
+            .unwrap();
+
+        let coins = (
+            Addr::unchecked("kujira1a"),
+            vec![Coin::new(5u128, "uusk"), Coin::new(10u128, "ukuji")],
+        );
+        assert_eq!(coins.encode(), "kujira1a,10ukuji,5uusk");
+    }
+
+    #[test]
+    fn domain_separated() {
+        let leaves = ["a", "b", "c", "d"];
+        let plain = MerkleTree::new(&leaves).unwrap();
+        let tree = MerkleTree::with_mode(&leaves, HashMode::DomainSeparated).unwrap();
+        assert_ne!(plain.root(), tree.root());
+
+        let merkle = Merkle::with_mode(tree.root(), HashMode::DomainSeparated).unwrap();
+        for (i, leaf) in leaves.iter().enumerate() {
+            merkle.verify_leaf(tree.proof(i).unwrap(), leaf).unwrap();
+        }
+        assert_eq!(
+            Merkle::new(tree.root())
+                .unwrap()
+                .verify_leaf(tree.proof(0).unwrap(), "a"),
+            Err(Error::VerificationFailed {})
+        );
+    }
+
+    #[test]
+    fn multi_proofs_verify() {
+        for n in 1..=13 {
+            let leaves: Vec<String> = (0..n).map(|i| format!("kujira1addr{i},{i}00")).collect();
+            let tree = MerkleTree::with_mode(&leaves, HashMode::DomainSeparated).unwrap();
+            let merkle = tree.merkle();
+            // Every subset of leaves
+            for mask in 1u32..(1 << n) {
+                let indices: Vec<usize> = (0..n).filter(|i| mask & (1 << i) != 0).collect();
+                let subset: Vec<&String> = indices.iter().map(|i| &leaves[*i]).collect();
+                let proof = tree.multi_proof(&indices).unwrap();
+                merkle.verify_multi(proof.clone(), &subset).unwrap();
+
+                if indices.len() < n {
+                    let missing = (0..n).find(|i| !indices.contains(i)).unwrap();
+                    let mut wrong = subset.clone();
+                    wrong[0] = &leaves[missing];
+                    assert!(merkle.verify_multi(proof, &wrong).is_err());
+                }
+            }
+            assert_eq!(tree.multi_proof(&[]), None);
+            assert_eq!(tree.multi_proof(&[n]), None);
+        }
+    }
+
+    #[test]
+    fn multi_proof_shares_siblings() {
+        let leaves: Vec<String> = (0..8).map(|i| i.to_string()).collect();
+        let tree = MerkleTree::new(&leaves).unwrap();
+        let proof = tree.multi_proof(&[0, 1, 2, 3]).unwrap();
+        assert_eq!(proof.proof, vec![tree.proof(0).unwrap()[2].clone()]);
+        assert_eq!(
+            tree.multi_proof(&[3, 0, 3, 1, 2]).unwrap(),
+            tree.multi_proof(&[0, 1, 2, 3]).unwrap()
+        );
+    }
+
+    #[test]
+    fn stored_merkle_defaults_to_plain() {
+        let merkle: Merkle =
+            cosmwasm_std::from_json(format!("{{\"root\":\"{}\"}}", hex::encode([0u8; 32])))
+                .unwrap();
+        assert_eq!(merkle.mode, HashMode::Plain);
+    }
+}
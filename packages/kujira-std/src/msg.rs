@@ -1,7 +1,7 @@
 //!    Bindings for message execution on Kujira Core
 
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{Addr, Coin, CosmosMsg, CustomMsg, Timestamp, Uint128};
+use cosmwasm_std::{Addr, Coin, CosmosMsg, CustomMsg, DenomMetadata, Timestamp, Uint128};
 
 use crate::{denom::Denom, ica::IcaMsg};
 
@@ -60,6 +60,20 @@ pub enum DenomMsg {
         denom: Denom,
         amount: Uint128,
     },
+    /// Sets the bank metadata of a denom. Only callable by its admin,
+    /// and `metadata.base` must match `denom`
+    SetMetadata {
+        denom: Denom,
+        metadata: DenomMetadata,
+    },
+    /// Moves `amount` of `denom` between two accounts, without the sender's consent.
+    /// Only callable by the denom admin
+    ForceTransfer {
+        denom: Denom,
+        amount: Uint128,
+        from_address: Addr,
+        to_address: Addr,
+    },
 }
 
 impl From<DenomMsg> for CosmosMsg<KujiraMsg> {
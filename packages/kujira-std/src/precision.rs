@@ -1,5 +1,6 @@
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{Decimal, Decimal256, Fraction, Uint128, Uint256};
+use cosmwasm_std::{Decimal, Decimal256, Uint128, Uint256};
+use thiserror::Error;
 
 #[cw_serde]
 pub enum Precision {
@@ -13,63 +14,266 @@ impl Default for Precision {
     }
 }
 
+/// The direction to round in when a value falls between two valid ticks
+#[cw_serde]
+#[derive(Copy, Default)]
+pub enum Rounding {
+    #[default]
+    Floor,
+    Ceil,
+    /// To the nearest tick, and to the even tick when exactly halfway
+    HalfEven,
+}
+
+#[derive(Error, Debug, PartialEq)]
+pub enum PrecisionError {
+    #[error("Significant figures must be greater than zero")]
+    ZeroSignificantFigures {},
+
+    #[error("Overflow when rounding")]
+    Overflow {},
+
+    #[error("Value must be greater than zero")]
+    Zero {},
+
+    #[error("Value does not match the precision")]
+    Imprecise {},
+
+    #[error("Lot size must be greater than zero")]
+    ZeroLotSize {},
+}
+
 impl Precision {
+    /// `None` if `other` isn't a multiple of the precision, or the precision is invalid
     pub fn validate<T>(&self, other: &T) -> Option<()>
     where
         T: Precise + PartialEq,
     {
-        if &other.round(self) == other {
-            Some(())
-        } else {
-            None
+        match other.try_round(self) {
+            Ok(rounded) if &rounded == other => Some(()),
+            _ => None,
         }
     }
-}
 
-pub trait Precise {
-    fn round(&self, other: &Precision) -> Self;
-}
+    /// Snaps `value` to a valid tick in the direction of `rounding`, eg rounding
+    /// bids with [Rounding::Floor] and asks with [Rounding::Ceil]
+    pub fn snap<T: Precise>(&self, value: &T, rounding: Rounding) -> Result<T, PrecisionError> {
+        value.try_round_with(self, rounding)
+    }
 
-impl Precise for Decimal {
-    fn round(&self, p: &Precision) -> Self {
-        match p {
+    /// The number of trailing atomic digits to zero, for a value with `atomics` and
+    /// `decimals` fractional digits. Values with no more digits than the precision
+    /// allows are left as they are
+    fn truncated_digits(&self, atomics: Uint256, decimals: u32) -> Result<u32, PrecisionError> {
+        match self {
+            Precision::SignificantFigures(0) => Err(PrecisionError::ZeroSignificantFigures {}),
             Precision::SignificantFigures(sf) => {
-                let int = self.numerator();
-                let len = int.to_string().as_str().bytes().len() as u32;
-                let decimals: u32 = len - *sf as u32;
-                let pow = Uint128::from(10u128).pow(decimals);
-                let truncated = Uint128::one().mul_floor(Self::from_ratio(int, pow));
-                Self::from_ratio(truncated * pow, self.denominator())
-            }
-            Precision::DecimalPlaces(dp) => {
-                let pow = Uint128::from(10u128).pow(18 - *dp as u32);
-                let x = Self::from_ratio(self.numerator(), self.denominator() * pow);
-                Self::from_ratio(x.numerator() * pow, x.denominator())
+                let len = atomics.to_string().len() as u32;
+                Ok(len.saturating_sub(*sf as u32))
             }
+            Precision::DecimalPlaces(dp) => Ok(decimals.saturating_sub(*dp as u32)),
         }
     }
 }
 
+pub trait Precise: Sized + Clone {
+    fn try_round_with(&self, other: &Precision, rounding: Rounding)
+        -> Result<Self, PrecisionError>;
+
+    /// Rounds down to the precision
+    fn try_round(&self, other: &Precision) -> Result<Self, PrecisionError> {
+        self.try_round_with(other, Rounding::Floor)
+    }
+
+    /// Rounds down to the precision. An invalid precision is ignored and the value
+    /// returned unchanged, use [Precise::try_round] to handle it
+    fn round(&self, other: &Precision) -> Self {
+        self.try_round(other).unwrap_or_else(|_| self.clone())
+    }
+}
+
+/// Rounds `atomics` to a multiple of `10^digits`
+fn quantize(atomics: Uint256, digits: u32, rounding: Rounding) -> Result<Uint256, PrecisionError> {
+    let pow = Uint256::from(10u128)
+        .checked_pow(digits)
+        .map_err(|_| PrecisionError::Overflow {})?;
+    let quotient = atomics / pow;
+    let remainder = atomics % pow;
+    let up = match rounding {
+        Rounding::Floor => false,
+        Rounding::Ceil => !remainder.is_zero(),
+        Rounding::HalfEven => {
+            let twice = remainder * Uint256::from(2u128);
+            twice > pow || (twice == pow && quotient % Uint256::from(2u128) == Uint256::one())
+        }
+    };
+    let quotient = if up {
+        quotient + Uint256::one()
+    } else {
+        quotient
+    };
+    quotient
+        .checked_mul(pow)
+        .map_err(|_| PrecisionError::Overflow {})
+}
+
+impl Precise for Decimal {
+    fn try_round_with(&self, p: &Precision, rounding: Rounding) -> Result<Self, PrecisionError> {
+        let atomics = Uint256::from(self.atomics());
+        let rounded = quantize(atomics, p.truncated_digits(atomics, 18)?, rounding)?;
+        Uint128::try_from(rounded)
+            .map(Self::new)
+            .map_err(|_| PrecisionError::Overflow {})
+    }
+}
+
 impl Precise for Decimal256 {
-    fn round(&self, p: &Precision) -> Self {
-        match p {
-            Precision::SignificantFigures(sf) => {
-                let int = self.numerator();
-                let len = int.to_string().as_str().bytes().len() as u32;
-                let decimals: u32 = len - *sf as u32;
-                let pow = Uint256::from(10u128).pow(decimals);
-                let truncated = Uint256::one().mul_floor(Self::from_ratio(int, pow));
-                Self::from_ratio(truncated * pow, self.denominator())
-            }
-            Precision::DecimalPlaces(dp) => {
-                let pow = Uint256::from(10u128).pow(18 - *dp as u32);
-                let x = Self::from_ratio(self.numerator(), self.denominator() * pow);
-                Self::from_ratio(x.numerator() * pow, x.denominator())
-            }
+    fn try_round_with(&self, p: &Precision, rounding: Rounding) -> Result<Self, PrecisionError> {
+        let atomics = self.atomics();
+        Ok(Self::new(quantize(
+            atomics,
+            p.truncated_digits(atomics, 18)?,
+            rounding,
+        )?))
+    }
+}
+
+/// Integers have no decimal places, so only [Precision::SignificantFigures] changes them
+impl Precise for Uint128 {
+    fn try_round_with(&self, p: &Precision, rounding: Rounding) -> Result<Self, PrecisionError> {
+        let atomics = Uint256::from(*self);
+        let rounded = quantize(atomics, p.truncated_digits(atomics, 0)?, rounding)?;
+        Uint128::try_from(rounded).map_err(|_| PrecisionError::Overflow {})
+    }
+}
+
+impl Precise for Uint256 {
+    fn try_round_with(&self, p: &Precision, rounding: Rounding) -> Result<Self, PrecisionError> {
+        quantize(*self, p.truncated_digits(*self, 0)?, rounding)
+    }
+}
+
+/// The prices a FIN book accepts. `precision` applies to the price in human units,
+/// ie after shifting by `decimal_delta`, the base decimals minus the quote decimals
+#[cw_serde]
+pub struct TickSize {
+    pub precision: Precision,
+    pub decimal_delta: i8,
+}
+
+impl TickSize {
+    pub fn new(precision: Precision, decimal_delta: i8) -> Self {
+        Self {
+            precision,
+            decimal_delta,
+        }
+    }
+
+    pub fn validate(&self, price: &Decimal256) -> Result<(), PrecisionError> {
+        if price.is_zero() {
+            return Err(PrecisionError::Zero {});
+        }
+        let human = self.to_human(price)?;
+        if human.try_round(&self.precision)? != human {
+            return Err(PrecisionError::Imprecise {});
+        }
+        Ok(())
+    }
+
+    /// Snaps `price` to a valid tick in the direction of `rounding`
+    pub fn snap(
+        &self,
+        price: &Decimal256,
+        rounding: Rounding,
+    ) -> Result<Decimal256, PrecisionError> {
+        let human = self
+            .to_human(price)?
+            .try_round_with(&self.precision, rounding)?;
+        self.to_raw(&human)
+    }
+
+    fn shift(&self) -> Result<Decimal256, PrecisionError> {
+        Uint256::from(10u128)
+            .checked_pow(u32::from(self.decimal_delta.unsigned_abs()))
+            .ok()
+            .and_then(|pow| Decimal256::checked_from_ratio(pow, 1u128).ok())
+            .ok_or(PrecisionError::Overflow {})
+    }
+
+    fn to_human(&self, price: &Decimal256) -> Result<Decimal256, PrecisionError> {
+        if self.decimal_delta >= 0 {
+            price
+                .checked_mul(self.shift()?)
+                .map_err(|_| PrecisionError::Overflow {})
+        } else {
+            price
+                .checked_div(self.shift()?)
+                .map_err(|_| PrecisionError::Overflow {})
+        }
+    }
+
+    fn to_raw(&self, human: &Decimal256) -> Result<Decimal256, PrecisionError> {
+        if self.decimal_delta >= 0 {
+            human
+                .checked_div(self.shift()?)
+                .map_err(|_| PrecisionError::Overflow {})
+        } else {
+            human
+                .checked_mul(self.shift()?)
+                .map_err(|_| PrecisionError::Overflow {})
         }
     }
 }
 
+/// The increment order quantities must be a multiple of
+#[cw_serde]
+#[derive(Copy)]
+pub struct LotSize(pub Uint256);
+
+impl LotSize {
+    pub fn validate(&self, quantity: impl Into<Uint256>) -> Result<(), PrecisionError> {
+        let quantity = quantity.into();
+        if quantity.is_zero() {
+            return Err(PrecisionError::Zero {});
+        }
+        if self.snap(quantity, Rounding::Floor)? != quantity {
+            return Err(PrecisionError::Imprecise {});
+        }
+        Ok(())
+    }
+
+    /// Snaps `quantity` to a multiple of the lot size in the direction of `rounding`
+    pub fn snap(
+        &self,
+        quantity: impl Into<Uint256>,
+        rounding: Rounding,
+    ) -> Result<Uint256, PrecisionError> {
+        if self.0.is_zero() {
+            return Err(PrecisionError::ZeroLotSize {});
+        }
+        let quantity = quantity.into();
+        let lots = quantity / self.0;
+        let remainder = quantity % self.0;
+        let up = match rounding {
+            Rounding::Floor => false,
+            Rounding::Ceil => !remainder.is_zero(),
+            Rounding::HalfEven => {
+                let twice = remainder.checked_mul(Uint256::from(2u128));
+                match twice {
+                    Ok(twice) => {
+                        twice > self.0
+                            || (twice == self.0 && lots % Uint256::from(2u128) == Uint256::one())
+                    }
+                    Err(_) => true,
+                }
+            }
+        };
+        let lots = if up { lots + Uint256::one() } else { lots };
+        lots.checked_mul(self.0)
+            .map_err(|_| PrecisionError::Overflow {})
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -92,4 +296,187 @@ mod tests {
         assert_eq!(p.validate(&Decimal::from_str("12.343").unwrap()), None);
         assert_eq!(p.validate(&Decimal::from_str("1.2").unwrap()), Some(()));
     }
+
+    #[test]
+    fn test_rounding_modes() {
+        let dp = Precision::DecimalPlaces(2);
+        let x = Decimal::from_str("1.235").unwrap();
+        assert_eq!(x.round(&dp), Decimal::from_str("1.23").unwrap());
+        assert_eq!(
+            x.try_round_with(&dp, Rounding::Ceil),
+            Ok(Decimal::from_str("1.24").unwrap())
+        );
+        assert_eq!(
+            x.try_round_with(&dp, Rounding::HalfEven),
+            Ok(Decimal::from_str("1.24").unwrap())
+        );
+        assert_eq!(
+            Decimal::from_str("1.245")
+                .unwrap()
+                .try_round_with(&dp, Rounding::HalfEven),
+            Ok(Decimal::from_str("1.24").unwrap())
+        );
+        assert_eq!(
+            Decimal::from_str("1.2451")
+                .unwrap()
+                .try_round_with(&dp, Rounding::HalfEven),
+            Ok(Decimal::from_str("1.25").unwrap())
+        );
+        assert_eq!(
+            Decimal::from_str("1.23")
+                .unwrap()
+                .try_round_with(&dp, Rounding::Ceil),
+            Ok(Decimal::from_str("1.23").unwrap())
+        );
+
+        let sf = Precision::SignificantFigures(3);
+        let x = Decimal256::from_str("99.95").unwrap();
+        assert_eq!(x.round(&sf), Decimal256::from_str("99.9").unwrap());
+        assert_eq!(
+            sf.snap(&x, Rounding::Ceil),
+            Ok(Decimal256::from_str("100").unwrap())
+        );
+        assert_eq!(
+            sf.snap(&x, Rounding::HalfEven),
+            Ok(Decimal256::from_str("100").unwrap())
+        );
+        assert_eq!(
+            sf.snap(&Decimal256::from_str("0.012345").unwrap(), Rounding::Ceil),
+            Ok(Decimal256::from_str("0.0124").unwrap())
+        );
+    }
+
+    #[test]
+    fn test_tiny_values() {
+        let p = Precision::SignificantFigures(3);
+        let tiny = Decimal::from_str("0.000000000000000012").unwrap();
+        assert_eq!(tiny.try_round(&p), Ok(tiny));
+        assert_eq!(p.validate(&tiny), Some(()));
+        assert_eq!(p.validate(&Decimal::zero()), Some(()));
+        assert_eq!(
+            Decimal256::from_str("0.000000000000000123")
+                .unwrap()
+                .try_round_with(&Precision::SignificantFigures(2), Rounding::Ceil),
+            Ok(Decimal256::from_str("0.00000000000000013").unwrap())
+        );
+
+        let p = Precision::DecimalPlaces(24);
+        assert_eq!(p.validate(&tiny), Some(()));
+    }
+
+    #[test]
+    fn test_try_round_errors() {
+        let p = Precision::SignificantFigures(0);
+        let x = Decimal::from_str("1.5").unwrap();
+        assert_eq!(
+            x.try_round(&p),
+            Err(PrecisionError::ZeroSignificantFigures {})
+        );
+        assert_eq!(x.round(&p), x);
+        assert_eq!(
+            p.snap(&x, Rounding::Ceil),
+            Err(PrecisionError::ZeroSignificantFigures {})
+        );
+        assert_eq!(p.validate(&x), None);
+
+        assert_eq!(
+            Decimal::MAX.try_round_with(&Precision::SignificantFigures(2), Rounding::Ceil),
+            Err(PrecisionError::Overflow {})
+        );
+        assert_eq!(
+            Decimal256::MAX.try_round_with(&Precision::DecimalPlaces(0), Rounding::Ceil),
+            Err(PrecisionError::Overflow {})
+        );
+        assert_eq!(
+            Decimal::MAX.try_round(&Precision::SignificantFigures(2)),
+            Ok(Decimal::from_str("340000000000000000000").unwrap())
+        );
+    }
+
+    #[test]
+    fn test_integers() {
+        let p = Precision::SignificantFigures(2);
+        assert_eq!(Uint128::from(12345u128).round(&p), Uint128::from(12000u128));
+        assert_eq!(
+            Uint256::from(12345u128).try_round_with(&p, Rounding::Ceil),
+            Ok(Uint256::from(13000u128))
+        );
+        assert_eq!(p.validate(&Uint128::from(7u128)), Some(()));
+        assert_eq!(
+            Precision::DecimalPlaces(2).validate(&Uint128::from(12345u128)),
+            Some(())
+        );
+        assert_eq!(
+            Uint128::MAX.try_round_with(&p, Rounding::Ceil),
+            Err(PrecisionError::Overflow {})
+        );
+    }
+
+    #[test]
+    fn test_tick_size() {
+        // base has 6 decimals, quote has 8
+        let tick = TickSize::new(Precision::DecimalPlaces(2), -2);
+        assert_eq!(tick.validate(&Decimal256::from_str("123").unwrap()), Ok(()));
+        assert_eq!(
+            tick.validate(&Decimal256::from_str("1.23").unwrap()),
+            Err(PrecisionError::Imprecise {})
+        );
+        assert_eq!(
+            tick.validate(&Decimal256::zero()),
+            Err(PrecisionError::Zero {})
+        );
+        assert_eq!(
+            tick.snap(&Decimal256::from_str("1.23").unwrap(), Rounding::Ceil),
+            Ok(Decimal256::from_str("2").unwrap())
+        );
+
+        let tick = TickSize::new(Precision::SignificantFigures(3), 6);
+        let price = Decimal256::from_str("0.000012345").unwrap();
+        let snapped = tick.snap(&price, Rounding::Floor).unwrap();
+        assert_eq!(snapped, Decimal256::from_str("0.0000123").unwrap());
+        assert_eq!(tick.validate(&snapped), Ok(()));
+        assert_eq!(tick.validate(&price), Err(PrecisionError::Imprecise {}));
+
+        // 10^60 doesn't fit in a Decimal256, and 10^80 not even in its atomics
+        for delta in [60, -80, i8::MIN] {
+            let tick = TickSize::new(Precision::DecimalPlaces(2), delta);
+            assert_eq!(
+                tick.validate(&Decimal256::one()),
+                Err(PrecisionError::Overflow {})
+            );
+        }
+
+        let tick = TickSize::new(Precision::SignificantFigures(0), 0);
+        assert_eq!(
+            tick.validate(&Decimal256::one()),
+            Err(PrecisionError::ZeroSignificantFigures {})
+        );
+    }
+
+    #[test]
+    fn test_lot_size() {
+        let lot = LotSize(Uint256::from(1000u128));
+        assert_eq!(lot.validate(Uint128::from(5000u128)), Ok(()));
+        assert_eq!(
+            lot.validate(Uint128::from(5500u128)),
+            Err(PrecisionError::Imprecise {})
+        );
+        assert_eq!(lot.validate(Uint128::zero()), Err(PrecisionError::Zero {}));
+        assert_eq!(
+            lot.snap(Uint128::from(5500u128), Rounding::HalfEven),
+            Ok(Uint256::from(6000u128))
+        );
+        assert_eq!(
+            lot.snap(Uint128::from(4500u128), Rounding::HalfEven),
+            Ok(Uint256::from(4000u128))
+        );
+        assert_eq!(
+            lot.snap(Uint128::from(4001u128), Rounding::Ceil),
+            Ok(Uint256::from(5000u128))
+        );
+        assert_eq!(
+            LotSize(Uint256::zero()).validate(Uint128::one()),
+            Err(PrecisionError::ZeroLotSize {})
+        );
+    }
 }
@@ -4,7 +4,10 @@ use std::{
 };
 
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{Decimal, Fraction, StdResult, Uint128};
+use cosmwasm_std::{
+    CheckedFromRatioError, CheckedMultiplyFractionError, Decimal, DivideByZeroError, Fraction,
+    StdError, StdResult, Uint128, Uint256,
+};
 
 use crate::querier::KujiraQuerier;
 
@@ -90,6 +93,59 @@ impl NormalizedPrice {
     pub fn inner(&self) -> Decimal {
         self.0
     }
+
+    pub fn checked_mul(self, rhs: NormalizedPrice) -> StdResult<Self> {
+        self.0
+            .checked_mul(rhs.0)
+            .map(Self)
+            .map_err(StdError::overflow)
+    }
+
+    pub fn checked_div(self, rhs: NormalizedPrice) -> StdResult<Self> {
+        self.0.checked_div(rhs.0).map(Self).map_err(|e| match e {
+            CheckedFromRatioError::DivideByZero => {
+                StdError::divide_by_zero(DivideByZeroError::new())
+            }
+            // `OverflowOperation` has no division variant to report this with
+            CheckedFromRatioError::Overflow => {
+                StdError::generic_err(format!("Division overflow: {} / {}", self.0, rhs.0))
+            }
+        })
+    }
+
+    /// The value of `amount` at this price, rounded down
+    pub fn checked_mul_uint(self, amount: Uint128) -> StdResult<Uint128> {
+        self.checked_mul_uint_floor(amount)
+    }
+
+    pub fn checked_mul_uint_floor(self, amount: Uint128) -> StdResult<Uint128> {
+        amount.checked_mul_floor(self.0).map_err(fraction_error)
+    }
+
+    pub fn checked_mul_uint_ceil(self, amount: Uint128) -> StdResult<Uint128> {
+        amount.checked_mul_ceil(self.0).map_err(fraction_error)
+    }
+
+    /// The amount worth `value` at this price, rounded down
+    pub fn checked_div_uint(self, value: Uint128) -> StdResult<Uint128> {
+        self.checked_div_uint_floor(value)
+    }
+
+    pub fn checked_div_uint_floor(self, value: Uint128) -> StdResult<Uint128> {
+        value.checked_div_floor(self.0).map_err(fraction_error)
+    }
+
+    pub fn checked_div_uint_ceil(self, value: Uint128) -> StdResult<Uint128> {
+        value.checked_div_ceil(self.0).map_err(fraction_error)
+    }
+}
+
+fn fraction_error(err: CheckedMultiplyFractionError) -> StdError {
+    match err {
+        CheckedMultiplyFractionError::DivideByZero(e) => StdError::divide_by_zero(e),
+        CheckedMultiplyFractionError::Overflow(e) => StdError::overflow(e),
+        CheckedMultiplyFractionError::ConversionOverflow(e) => e.into(),
+    }
 }
 
 impl Deref for NormalizedPrice {
@@ -171,11 +227,101 @@ impl Div<NormalizedPrice> for Uint128 {
         rhs.0.inv().map(|inv| self.mul_floor(inv))
     }
 }
+
+/// The exchange rate between two oracle denoms, `base` priced in `quote`.
+///
+/// Both sides are normalized, so amounts convert correctly between denoms
+/// with different decimals
+#[cw_serde]
+#[derive(Copy, Eq)]
+pub struct PairPrice {
+    base: NormalizedPrice,
+    quote: NormalizedPrice,
+}
+
+impl PairPrice {
+    pub fn new(base: NormalizedPrice, quote: NormalizedPrice) -> StdResult<Self> {
+        if base.is_zero() || quote.is_zero() {
+            return Err(StdError::generic_err("Pair price requires non-zero prices"));
+        }
+        Ok(Self { base, quote })
+    }
+
+    pub fn from_oracle<B: Into<String>, Q: Into<String>>(
+        querier: &KujiraQuerier,
+        base_denom: B,
+        base_decimals: u8,
+        quote_denom: Q,
+        quote_decimals: u8,
+    ) -> StdResult<Self> {
+        Self::new(
+            NormalizedPrice::from_oracle(querier, base_denom, base_decimals)?,
+            NormalizedPrice::from_oracle(querier, quote_denom, quote_decimals)?,
+        )
+    }
+
+    pub fn base(&self) -> NormalizedPrice {
+        self.base
+    }
+
+    pub fn quote(&self) -> NormalizedPrice {
+        self.quote
+    }
+
+    /// The price of one unit of base, in units of quote
+    pub fn rate(&self) -> StdResult<NormalizedPrice> {
+        self.base.checked_div(self.quote)
+    }
+
+    /// Swaps base and quote
+    pub fn inverse(&self) -> Self {
+        Self {
+            base: self.quote,
+            quote: self.base,
+        }
+    }
+
+    /// The amount of quote worth `amount` of base, rounded down
+    pub fn base_to_quote(&self, amount: Uint128) -> StdResult<Uint128> {
+        convert(amount, self.base, self.quote, false)
+    }
+
+    pub fn base_to_quote_ceil(&self, amount: Uint128) -> StdResult<Uint128> {
+        convert(amount, self.base, self.quote, true)
+    }
+
+    /// The amount of base worth `amount` of quote, rounded down
+    pub fn quote_to_base(&self, amount: Uint128) -> StdResult<Uint128> {
+        convert(amount, self.quote, self.base, false)
+    }
+
+    pub fn quote_to_base_ceil(&self, amount: Uint128) -> StdResult<Uint128> {
+        convert(amount, self.quote, self.base, true)
+    }
+}
+
+/// `amount * from / to`, without rounding the intermediate rate
+fn convert(
+    amount: Uint128,
+    from: NormalizedPrice,
+    to: NormalizedPrice,
+    ceil: bool,
+) -> StdResult<Uint128> {
+    // Both prices share the same denominator, so the ratio of their atomics is exact
+    let numerator = amount.full_mul(from.atomics());
+    let denominator = Uint256::from(to.atomics());
+    let mut result = numerator.checked_div(denominator)?;
+    if ceil && !numerator.checked_rem(denominator)?.is_zero() {
+        result += Uint256::one();
+    }
+    Ok(result.try_into()?)
+}
+
 #[cfg(test)]
 mod tests {
-    use cosmwasm_std::Decimal;
+    use cosmwasm_std::{Decimal, StdError, Uint128};
 
-    use super::{HumanPrice, NormalizedPrice};
+    use super::{HumanPrice, NormalizedPrice, PairPrice};
 
     #[test]
     fn serialize_human_price() {
@@ -204,4 +350,94 @@ mod tests {
         let deserialized: NormalizedPrice = serde_json::from_str(r#""4.59""#).unwrap();
         assert_eq!(price, deserialized);
     }
+
+    #[test]
+    fn checked_arithmetic() {
+        let price = NormalizedPrice(Decimal::percent(150));
+        let amount = Uint128::from(101u128);
+        assert_eq!(
+            price.checked_mul_uint(amount).unwrap(),
+            Uint128::from(151u128)
+        );
+        assert_eq!(
+            price.checked_mul_uint_ceil(amount).unwrap(),
+            Uint128::from(152u128)
+        );
+        assert_eq!(
+            price.checked_div_uint(amount).unwrap(),
+            Uint128::from(67u128)
+        );
+        assert_eq!(
+            price.checked_div_uint_ceil(amount).unwrap(),
+            Uint128::from(68u128)
+        );
+        assert_eq!(
+            price.checked_div(NormalizedPrice(Decimal::percent(50))),
+            Ok(NormalizedPrice(Decimal::percent(300)))
+        );
+
+        let max = NormalizedPrice(Decimal::MAX);
+        assert!(matches!(
+            max.checked_mul(price),
+            Err(StdError::Overflow { .. })
+        ));
+        assert!(matches!(
+            max.checked_mul_uint(Uint128::MAX),
+            Err(StdError::ConversionOverflow { .. })
+        ));
+        assert!(matches!(
+            max.checked_div(NormalizedPrice(Decimal::percent(1))),
+            Err(StdError::GenericErr { msg, .. }) if msg.starts_with("Division overflow")
+        ));
+
+        let zero = NormalizedPrice(Decimal::zero());
+        assert!(matches!(
+            price.checked_div(zero),
+            Err(StdError::DivideByZero { .. })
+        ));
+        assert!(matches!(
+            zero.checked_div_uint(amount),
+            Err(StdError::DivideByZero { .. })
+        ));
+    }
+
+    #[test]
+    fn pair_price() {
+        // 10 USD per 6dp base, 2 USD per 8dp quote
+        let base = HumanPrice(Decimal::percent(1000)).normalize(6);
+        let quote = HumanPrice(Decimal::percent(200)).normalize(8);
+        let pair = PairPrice::new(base, quote).unwrap();
+
+        // 1 base is 5 quote, with two more decimals
+        assert_eq!(
+            pair.rate().unwrap(),
+            NormalizedPrice(Decimal::from_ratio(500u128, 1u128))
+        );
+        assert_eq!(
+            pair.base_to_quote(Uint128::from(1_000_000u128)).unwrap(),
+            Uint128::from(500_000_000u128)
+        );
+        assert_eq!(
+            pair.quote_to_base(Uint128::from(500_000_000u128)).unwrap(),
+            Uint128::from(1_000_000u128)
+        );
+        assert_eq!(
+            pair.quote_to_base(Uint128::from(499u128)).unwrap(),
+            Uint128::zero()
+        );
+        assert_eq!(
+            pair.quote_to_base_ceil(Uint128::from(499u128)).unwrap(),
+            Uint128::one()
+        );
+        assert_eq!(
+            pair.inverse().rate().unwrap(),
+            NormalizedPrice(Decimal::permille(2))
+        );
+
+        assert!(matches!(
+            pair.base_to_quote(Uint128::MAX),
+            Err(StdError::ConversionOverflow { .. })
+        ));
+        assert!(PairPrice::new(base, NormalizedPrice(Decimal::zero())).is_err());
+    }
 }
@@ -1,13 +1,16 @@
 //!    Custom querier implementation for Kujira's chain core bindings
 
+use std::collections::BTreeMap;
+
 use cosmwasm_std::{Addr, Binary, Deps, DepsMut, QuerierWrapper, QueryRequest, StdResult};
 
 use crate::{
     denom::Denom,
     price::HumanPrice,
     query::{
-        AccountAddressResponse, BankQuery, ExchangeRateResponse, IbcVerifyQuery, IbcVerifyResponse,
-        IcaQuery, KujiraQuery, OracleQuery, SupplyResponse,
+        AccountAddressResponse, BankQuery, DenomAdminResponse, DenomQuery, ExchangeRateResponse,
+        FullDenomResponse, IbcVerifyQuery, IbcVerifyResponse, IcaQuery, KujiraQuery, OracleQuery,
+        SupplyResponse,
     },
 };
 
@@ -34,12 +37,55 @@ impl<'a> KujiraQuerier<'a> {
         Ok(result.rate.into())
     }
 
+    /// Queries the oracle module for the exchange rates of many denoms, keyed by denom.
+    /// The chain has no batch binding, so this makes one `ExchangeRate` query per denom.
+    /// As with `query_exchange_rate`, normalize before use.
+    pub fn query_exchange_rates<T: AsRef<str>>(
+        &self,
+        denoms: &[T],
+    ) -> StdResult<BTreeMap<String, HumanPrice>> {
+        denoms
+            .iter()
+            .map(|denom| {
+                let rate = self.query_exchange_rate(denom.as_ref())?;
+                Ok((denom.as_ref().to_string(), rate))
+            })
+            .collect()
+    }
+
     pub fn query_supply_of(&self, denom: Denom) -> StdResult<SupplyResponse> {
         let query = KujiraQuery::Bank(BankQuery::Supply { denom });
         let request: QueryRequest<KujiraQuery> = KujiraQuery::into(query);
         self.querier.query(&request)
     }
 
+    /// Queries the denom module for the full denom of `subdenom`, created by `creator`.
+    pub fn query_full_denom<T: Into<String>>(
+        &self,
+        creator: &Addr,
+        subdenom: T,
+    ) -> StdResult<Denom> {
+        let query = KujiraQuery::Denom(DenomQuery::FullDenom {
+            creator_addr: creator.clone(),
+            subdenom: subdenom.into(),
+        });
+        let request: QueryRequest<KujiraQuery> = KujiraQuery::into(query);
+        let result: FullDenomResponse = self.querier.query(&request)?;
+
+        Ok(result.denom)
+    }
+
+    /// Queries the denom module for the admin of a Token Factory denom.
+    pub fn query_denom_admin(&self, denom: &Denom) -> StdResult<Addr> {
+        let query = KujiraQuery::Denom(DenomQuery::DenomAdmin {
+            subdenom: denom.to_string(),
+        });
+        let request: QueryRequest<KujiraQuery> = KujiraQuery::into(query);
+        let result: DenomAdminResponse = self.querier.query(&request)?;
+
+        Ok(result.admin)
+    }
+
     pub fn inner(&self) -> &QuerierWrapper<'a, KujiraQuery> {
         self.querier
     }
@@ -30,6 +30,7 @@ pub enum DenomQuery {
         subdenom: String,
     },
     /// Returns the admin of a denom, if the denom is a Token Factory denom.
+    /// `subdenom` is the full denom, eg `factory/{creator}/{subdenom}`.
     DenomAdmin { subdenom: String },
 }
 
@@ -1,7 +1,11 @@
-use std::cmp::{max, min};
+use std::{
+    cmp::{max, min, Ordering},
+    iter::once,
+};
 
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{Decimal, Decimal256, Fraction, Timestamp, Uint128, Uint256};
+use cosmwasm_std::{Decimal, Decimal256, Timestamp, Uint128, Uint256, Uint512};
+use thiserror::Error;
 
 #[cw_serde]
 pub struct Schedule {
@@ -15,11 +19,214 @@ pub struct Schedule {
 pub enum Release {
     Fixed,
     Decay,
+    /// Linear, but nothing unlocks until `cliff`, when everything accrued so far unlocks at once
+    Cliff {
+        cliff: Timestamp,
+    },
+    /// An equal share unlocks every `interval` seconds after start, with the remainder at end
+    Step {
+        interval: u64,
+    },
+    /// The emission rate halves every `half_life` seconds. Linear within each half-life
+    HalfLife {
+        half_life: u64,
+    },
+    /// Linear between checkpoints of the cumulative share released. The curve starts at
+    /// 0 at start and ends at 1 at end, so checkpoints must be increasing in both fields
+    Piecewise {
+        checkpoints: Vec<Checkpoint>,
+    },
 }
 
+#[cw_serde]
+pub struct Checkpoint {
+    /// Seconds after the schedule start
+    pub offset: u64,
+    /// The share of the amount released by `offset`
+    pub share: Decimal,
+}
+
+#[derive(Error, Debug, PartialEq)]
+pub enum ScheduleError {
+    #[error("Schedule must end after it starts")]
+    InvalidDuration {},
+
+    #[error("Cliff must be between start and end")]
+    InvalidCliff {},
+
+    #[error("Step interval must be greater than zero")]
+    ZeroInterval {},
+
+    #[error("Half-life must be greater than zero")]
+    ZeroHalfLife {},
+
+    #[error("Checkpoints must be increasing, within the schedule, and release at most everything")]
+    InvalidCheckpoints {},
+}
+
+/// Half-lives after which nothing of a `Uint128` amount remains to release
+const MAX_HALF_LIVES: u64 = 128;
+
 impl Schedule {
+    pub fn new(
+        start: Timestamp,
+        end: Timestamp,
+        amount: Uint128,
+        release: Release,
+    ) -> Result<Self, ScheduleError> {
+        let schedule = Self {
+            start,
+            end,
+            amount,
+            release,
+        };
+        schedule.validate()?;
+        Ok(schedule)
+    }
+
+    /// Checks a schedule that wasn't built with [Schedule::new], eg one received in a message
+    pub fn validate(&self) -> Result<(), ScheduleError> {
+        if self.end <= self.start {
+            return Err(ScheduleError::InvalidDuration {});
+        }
+        match &self.release {
+            Release::Fixed | Release::Decay => {}
+            Release::Cliff { cliff } => {
+                if *cliff < self.start || *cliff > self.end {
+                    return Err(ScheduleError::InvalidCliff {});
+                }
+            }
+            Release::Step { interval } => {
+                if *interval == 0 {
+                    return Err(ScheduleError::ZeroInterval {});
+                }
+            }
+            Release::HalfLife { half_life } => {
+                if *half_life == 0 {
+                    return Err(ScheduleError::ZeroHalfLife {});
+                }
+            }
+            Release::Piecewise { checkpoints } => {
+                let total_duration = self.end.seconds() - self.start.seconds();
+                let mut prev = &Checkpoint {
+                    offset: 0,
+                    share: Decimal::zero(),
+                };
+                for checkpoint in checkpoints {
+                    if checkpoint.offset < prev.offset || checkpoint.share < prev.share {
+                        return Err(ScheduleError::InvalidCheckpoints {});
+                    }
+                    prev = checkpoint;
+                }
+                if prev.offset > total_duration || prev.share > Decimal::one() {
+                    return Err(ScheduleError::InvalidCheckpoints {});
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The amount released from the start of the schedule until `time`
+    pub fn released_until(&self, time: &Timestamp) -> Uint128 {
+        self.released(&self.start, time)
+    }
+
+    /// The amount still to be released after `time`
+    pub fn remaining(&self, time: &Timestamp) -> Uint128 {
+        self.amount.saturating_sub(self.released_until(time))
+    }
+
+    /// The release rate at `time`, per second. Step releases are discrete, so their rate is zero
+    pub fn rate_at(&self, time: &Timestamp) -> Decimal256 {
+        let start = self.start.seconds();
+        let end = self.end.seconds();
+        if time.seconds() < start || time.seconds() >= end {
+            return Decimal256::zero();
+        }
+        let elapsed = time.seconds() - start;
+        let total_duration = end - start;
+        let amount = Uint256::from(self.amount);
+        match &self.release {
+            Release::Fixed => Decimal256::from_ratio(amount, total_duration),
+            Release::Decay => Decimal256::from_ratio(
+                amount * Uint256::from(2u128) * Uint256::from(total_duration - elapsed),
+                Uint256::from(total_duration) * Uint256::from(total_duration),
+            ),
+            Release::Cliff { cliff } => {
+                if *time < *cliff {
+                    return Decimal256::zero();
+                }
+                Decimal256::from_ratio(amount, total_duration)
+            }
+            Release::Step { .. } => Decimal256::zero(),
+            Release::HalfLife { half_life } => {
+                let periods = elapsed / half_life;
+                if periods >= MAX_HALF_LIVES {
+                    return Decimal256::zero();
+                }
+                // The weight grows by 2^(scale - periods) per second in this half-life
+                let scale = min(total_duration / half_life, MAX_HALF_LIVES) as u32;
+                let atomics = (Uint512::from(self.amount)
+                    * Uint512::from(Decimal256::one().atomics()))
+                    << (scale - periods as u32);
+                let rate = atomics / half_life_weight(total_duration, *half_life, scale);
+                Uint256::try_from(rate)
+                    .map(Decimal256::new)
+                    .unwrap_or(Decimal256::MAX)
+            }
+            Release::Piecewise { checkpoints } => {
+                let mut prev = (0u64, Decimal::zero());
+                for (offset, share) in checkpoints
+                    .iter()
+                    .map(|c| (c.offset, c.share))
+                    .chain(once((total_duration, Decimal::one())))
+                {
+                    if elapsed < offset {
+                        return Decimal256::from_ratio(
+                            amount * Uint256::from(share.saturating_sub(prev.1).atomics()),
+                            Uint256::from(offset.saturating_sub(prev.0))
+                                * Uint256::from(Decimal::one().atomics()),
+                        );
+                    }
+                    prev = (offset, share);
+                }
+                Decimal256::zero()
+            }
+        }
+    }
+
+    /// The first time after `time` at which more is released, if any remains
+    pub fn next_unlock(&self, time: &Timestamp) -> Option<Timestamp> {
+        let current = self.released_until(time);
+        if self.released_until(&self.end) <= current {
+            return None;
+        }
+        // released_until(lo) == current < released_until(hi)
+        let mut lo = max(time.seconds(), self.start.seconds());
+        let mut hi = self.end.seconds();
+        while hi - lo > 1 {
+            let mid = lo + (hi - lo) / 2;
+            if self.released_until(&Timestamp::from_seconds(mid)) > current {
+                hi = mid;
+            } else {
+                lo = mid;
+            }
+        }
+        Some(Timestamp::from_seconds(hi))
+    }
+
+    /// Samples the cumulative release every `interval` seconds from `from`,
+    /// finishing with a sample at the end of the schedule
+    pub fn samples(&self, from: Timestamp, interval: u64) -> Samples<'_> {
+        Samples {
+            schedule: self,
+            next: Some(from.seconds()),
+            interval,
+        }
+    }
+
     pub fn released(&self, start: &Timestamp, end: &Timestamp) -> Uint128 {
-        if self.start.seconds() > end.seconds() {
+        if self.start.seconds() > end.seconds() || self.end <= self.start || self.amount.is_zero() {
             return Uint128::zero();
         }
         match self.release {
@@ -35,40 +242,133 @@ impl Schedule {
                 self.amount
                     .mul_floor(Decimal::from_ratio(duration, total_duration))
             }
-            Release::Decay => {
-                let total_duration = self.end.seconds() - self.start.seconds();
+            // The remaining curves are the difference of a monotonic cumulative release,
+            // so adjacent windows never sum past `amount`
+            _ => {
                 let start = max(self.start.seconds(), start.seconds());
                 let end = min(self.end.seconds(), end.seconds());
                 if end <= start {
                     return Uint128::zero();
                 }
-                let c = Decimal256::from_ratio(
-                    Uint256::from(self.amount) * Uint256::from(2u128),
-                    total_duration,
-                );
-                let div = Decimal256::from_ratio(total_duration * total_duration, self.amount);
-
-                let b = Uint256::from(end - self.start.seconds());
-                let a = Uint256::from(start - self.start.seconds());
-                let b = b.mul_floor(c)
-                    - Uint256::one().mul_floor(Decimal256::from_ratio(
-                        b * b * div.denominator(),
-                        div.numerator(),
-                    ));
-
-                let a = a.mul_floor(c)
-                    - Uint256::one().mul_floor(Decimal256::from_ratio(
-                        a * a * div.denominator(),
-                        div.numerator(),
-                    ));
-
-                let diff = b.checked_sub(a).unwrap_or_default();
-                diff.try_into().unwrap()
+                self.cumulative(end).saturating_sub(self.cumulative(start))
+            }
+        }
+    }
+
+    /// The amount released from start until `time`, rounded down
+    fn cumulative(&self, time: u64) -> Uint128 {
+        let start = self.start.seconds();
+        let end = self.end.seconds();
+        if time <= start {
+            return Uint128::zero();
+        }
+        if time >= end {
+            return self.amount;
+        }
+        let elapsed = time - start;
+        let total_duration = end - start;
+        match &self.release {
+            Release::Fixed => self.released(&self.start, &Timestamp::from_seconds(time)),
+            // amount * (2t/T - t²/T²), kept in integers so large amounts can't lose precision
+            Release::Decay => {
+                let elapsed = Uint256::from(elapsed);
+                let total_duration = Uint256::from(total_duration);
+                let weight = elapsed * (total_duration * Uint256::from(2u128) - elapsed);
+                Uint256::from(self.amount)
+                    .multiply_ratio(weight, total_duration * total_duration)
+                    .try_into()
+                    .unwrap_or(self.amount)
+            }
+            Release::Cliff { cliff } => {
+                if time < cliff.seconds() {
+                    return Uint128::zero();
+                }
+                self.amount.multiply_ratio(elapsed, total_duration)
+            }
+            Release::Step { interval } => {
+                if *interval == 0 {
+                    return self.amount;
+                }
+                let steps = total_duration.div_ceil(*interval);
+                self.amount.multiply_ratio(elapsed / interval, steps)
+            }
+            Release::HalfLife { half_life } => {
+                if *half_life == 0 {
+                    return self.amount;
+                }
+                let scale = min(total_duration / half_life, MAX_HALF_LIVES) as u32;
+                let released = Uint512::from(self.amount)
+                    * half_life_weight(elapsed, *half_life, scale)
+                    / half_life_weight(total_duration, *half_life, scale);
+                Uint256::try_from(released)
+                    .ok()
+                    .and_then(|x| Uint128::try_from(x).ok())
+                    .map_or(self.amount, |x| min(x, self.amount))
+            }
+            Release::Piecewise { checkpoints } => {
+                let one = Decimal::one().atomics();
+                let mut prev = (0u64, Uint128::zero());
+                for (offset, share) in checkpoints
+                    .iter()
+                    .map(|c| (c.offset, c.share.atomics()))
+                    .chain(once((total_duration, one)))
+                {
+                    if elapsed < offset {
+                        let share =
+                            prev.1
+                                .saturating_add(share.saturating_sub(prev.1).multiply_ratio(
+                                    elapsed.saturating_sub(prev.0),
+                                    max(offset.saturating_sub(prev.0), 1),
+                                ));
+                        return min(self.amount.multiply_ratio(share, one), self.amount);
+                    }
+                    prev = (offset, share);
+                }
+                self.amount
             }
         }
     }
 }
 
+/// Iterator over `(time, released_until(time))`, see [Schedule::samples]
+pub struct Samples<'a> {
+    schedule: &'a Schedule,
+    next: Option<u64>,
+    interval: u64,
+}
+
+impl Iterator for Samples<'_> {
+    type Item = (Timestamp, Uint128);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let time = self.next?;
+        let end = self.schedule.end.seconds();
+        self.next = match time.cmp(&end) {
+            Ordering::Less if self.interval > 0 => {
+                Some(min(time.saturating_add(self.interval), end))
+            }
+            Ordering::Less => Some(end),
+            _ => None,
+        };
+        let time = Timestamp::from_seconds(time);
+        Some((time, self.schedule.released_until(&time)))
+    }
+}
+
+/// The cumulative emission weight after `elapsed` seconds, scaled by `half_life * 2^scale`.
+/// Each half-life carries half the weight of the one before
+fn half_life_weight(elapsed: u64, half_life: u64, scale: u32) -> Uint512 {
+    let periods = min(elapsed / half_life, MAX_HALF_LIVES) as u32;
+    let remainder = if u64::from(periods) == MAX_HALF_LIVES {
+        0
+    } else {
+        elapsed % half_life
+    };
+    let whole =
+        ((Uint512::one() << (periods + 1)) - Uint512::from(2u64)) * Uint512::from(half_life);
+    (whole + Uint512::from(remainder)) << (scale - periods)
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -209,4 +509,252 @@ mod tests {
             &Timestamp::from_seconds(1710974700),
         );
     }
+
+    fn windows(s: &Schedule, step: u64, until: u64) -> Uint128 {
+        (0..until)
+            .step_by(step as usize)
+            .map(|t| {
+                s.released(
+                    &Timestamp::from_seconds(t),
+                    &Timestamp::from_seconds(t + step),
+                )
+            })
+            .sum()
+    }
+
+    fn schedule(release: Release) -> Schedule {
+        Schedule {
+            start: Timestamp::from_seconds(100),
+            end: Timestamp::from_seconds(1100),
+            amount: Uint128::from(1_000_003u128),
+            release,
+        }
+    }
+
+    fn at(s: &Schedule, end: u64) -> Uint128 {
+        s.released(&Timestamp::from_seconds(0), &Timestamp::from_seconds(end))
+    }
+
+    #[test]
+    fn cliff_schedule() {
+        let s = schedule(Release::Cliff {
+            cliff: Timestamp::from_seconds(350),
+        });
+        assert_eq!(at(&s, 349), Uint128::zero());
+        assert_eq!(at(&s, 350), Uint128::from(250_000u128));
+        assert_eq!(at(&s, 600), Uint128::from(500_001u128));
+        assert_eq!(at(&s, 2000), s.amount);
+        assert_eq!(windows(&s, 7, 1200), s.amount);
+    }
+
+    #[test]
+    fn step_schedule() {
+        // 1000 seconds in steps of 300: unlocks at 400, 700, 1000 and 1100
+        let s = schedule(Release::Step { interval: 300 });
+        assert_eq!(at(&s, 399), Uint128::zero());
+        assert_eq!(at(&s, 400), Uint128::from(250_000u128));
+        assert_eq!(at(&s, 1099), Uint128::from(750_002u128));
+        assert_eq!(at(&s, 1100), s.amount);
+        assert_eq!(windows(&s, 13, 1200), s.amount);
+    }
+
+    #[test]
+    fn half_life_schedule() {
+        // Four half-lives, weighted 8:4:2:1
+        let s = Schedule {
+            amount: Uint128::from(15_000u128),
+            ..schedule(Release::HalfLife { half_life: 250 })
+        };
+        assert_eq!(at(&s, 350), Uint128::from(8_000u128));
+        assert_eq!(at(&s, 225), Uint128::from(4_000u128));
+        assert_eq!(at(&s, 600), Uint128::from(12_000u128));
+        assert_eq!(at(&s, 850), Uint128::from(14_000u128));
+        assert_eq!(at(&s, 1100), s.amount);
+        assert_eq!(windows(&s, 11, 1200), s.amount);
+
+        // Far more half-lives than can be represented
+        let s = Schedule {
+            start: Timestamp::from_seconds(0),
+            end: Timestamp::from_seconds(1_000_000_000),
+            amount: Uint128::MAX,
+            release: Release::HalfLife { half_life: 1 },
+        };
+        assert_eq!(at(&s, 1), Uint128::from(1u128 << 127));
+        assert_eq!(at(&s, 1000), Uint128::MAX);
+    }
+
+    #[test]
+    fn piecewise_schedule() {
+        let s = schedule(Release::Piecewise {
+            checkpoints: vec![
+                Checkpoint {
+                    offset: 100,
+                    share: Decimal::percent(50),
+                },
+                Checkpoint {
+                    offset: 500,
+                    share: Decimal::percent(50),
+                },
+                Checkpoint {
+                    offset: 500,
+                    share: Decimal::percent(90),
+                },
+            ],
+        });
+        assert_eq!(at(&s, 150), Uint128::from(250_000u128));
+        assert_eq!(at(&s, 599), Uint128::from(500_001u128));
+        assert_eq!(at(&s, 600), Uint128::from(900_002u128));
+        assert_eq!(at(&s, 850), Uint128::from(950_002u128));
+        assert_eq!(windows(&s, 9, 1200), s.amount);
+    }
+
+    #[test]
+    fn adjacent_windows() {
+        for release in [Release::Fixed, Release::Decay] {
+            let s = schedule(release);
+            assert!(windows(&s, 7, 1200) <= s.amount);
+        }
+    }
+
+    #[test]
+    fn decay_edge_cases() {
+        let start = Timestamp::from_seconds(0);
+        let end = Timestamp::from_seconds(1000);
+        let empty = Schedule::new(start, end, Uint128::zero(), Release::Decay).unwrap();
+        assert_eq!(empty.released_until(&end), Uint128::zero());
+        assert_eq!(empty.remaining(&start), Uint128::zero());
+
+        // The squared duration no longer fits in a u64
+        let end = Timestamp::from_seconds(5_000_000_000);
+        let amount = Uint128::from(1_000_000_000_000u128);
+        let long = Schedule::new(start, end, amount, Release::Decay).unwrap();
+        assert_eq!(
+            long.released_until(&Timestamp::from_seconds(2_500_000_000)),
+            Uint128::from(750_000_000_000u128)
+        );
+        assert_eq!(long.released_until(&end), amount);
+
+        // The amount is larger than the squared duration in atomics
+        let end = Timestamp::from_seconds(3600);
+        let amount = Uint128::from(100_000_000_000_000_000_000_000_000u128);
+        let large = Schedule::new(start, end, amount, Release::Decay).unwrap();
+        let minute = Timestamp::from_seconds(60);
+        assert_eq!(
+            large.released_until(&minute),
+            Uint128::from(3_305_555_555_555_555_555_555_555u128)
+        );
+        assert_eq!(
+            large.released_until(&minute) + large.released(&minute, &end),
+            amount
+        );
+    }
+
+    #[test]
+    fn validation() {
+        let start = Timestamp::from_seconds(100);
+        let end = Timestamp::from_seconds(1100);
+        let amount = Uint128::from(1000u128);
+        let new = |end, release| Schedule::new(start, end, amount, release);
+
+        assert_eq!(
+            new(start, Release::Fixed),
+            Err(ScheduleError::InvalidDuration {})
+        );
+        assert_eq!(
+            new(
+                end,
+                Release::Cliff {
+                    cliff: Timestamp::from_seconds(1101)
+                }
+            ),
+            Err(ScheduleError::InvalidCliff {})
+        );
+        assert_eq!(
+            new(end, Release::Step { interval: 0 }),
+            Err(ScheduleError::ZeroInterval {})
+        );
+        assert_eq!(
+            new(end, Release::HalfLife { half_life: 0 }),
+            Err(ScheduleError::ZeroHalfLife {})
+        );
+        let checkpoint = |offset, share| Checkpoint {
+            offset,
+            share: Decimal::percent(share),
+        };
+        for checkpoints in [
+            vec![checkpoint(200, 50), checkpoint(100, 60)],
+            vec![checkpoint(100, 50), checkpoint(200, 40)],
+            vec![checkpoint(1001, 50)],
+            vec![checkpoint(100, 101)],
+        ] {
+            assert_eq!(
+                new(end, Release::Piecewise { checkpoints }),
+                Err(ScheduleError::InvalidCheckpoints {})
+            );
+        }
+        assert!(new(end, Release::Decay).is_ok());
+
+        // Unvalidated schedules release nothing rather than panic
+        let s = Schedule {
+            end: start,
+            ..new(end, Release::Fixed).unwrap()
+        };
+        assert_eq!(s.released_until(&end), Uint128::zero());
+    }
+
+    #[test]
+    fn remaining_and_rate() {
+        let s = schedule(Release::Fixed);
+        let t = Timestamp::from_seconds(600);
+        assert_eq!(s.released_until(&t), Uint128::from(500_001u128));
+        assert_eq!(s.remaining(&t), Uint128::from(500_002u128));
+        assert_eq!(
+            s.rate_at(&t),
+            Decimal256::from_ratio(1_000_003u128, 1000u128)
+        );
+        assert_eq!(
+            s.rate_at(&Timestamp::from_seconds(1100)),
+            Decimal256::zero()
+        );
+
+        let s = Schedule {
+            amount: Uint128::from(5000u128),
+            ..schedule(Release::Decay)
+        };
+        assert_eq!(s.rate_at(&s.start), Decimal256::percent(1000));
+        assert_eq!(s.rate_at(&t), Decimal256::percent(500));
+
+        let s = Schedule {
+            amount: Uint128::from(15_000u128),
+            ..schedule(Release::HalfLife { half_life: 250 })
+        };
+        assert_eq!(s.rate_at(&s.start), Decimal256::percent(3200));
+        assert_eq!(
+            s.rate_at(&Timestamp::from_seconds(850)),
+            Decimal256::percent(400)
+        );
+
+        let s = schedule(Release::Step { interval: 300 });
+        assert_eq!(s.rate_at(&t), Decimal256::zero());
+        assert_eq!(s.next_unlock(&t), Some(Timestamp::from_seconds(700)));
+        assert_eq!(
+            s.next_unlock(&Timestamp::from_seconds(0)),
+            Some(Timestamp::from_seconds(400))
+        );
+        assert_eq!(s.next_unlock(&s.end), None);
+    }
+
+    #[test]
+    fn sampling() {
+        let s = schedule(Release::Step { interval: 300 });
+        let samples: Vec<_> = s
+            .samples(Timestamp::from_seconds(0), 400)
+            .map(|(t, released)| (t.seconds(), released.u128()))
+            .collect();
+        assert_eq!(
+            samples,
+            vec![(0, 0), (400, 250_000), (800, 500_001), (1100, 1_000_003)]
+        );
+        assert_eq!(s.samples(s.start, 0).count(), 2);
+    }
 }
@@ -2,6 +2,9 @@ use cosmwasm_std::{Addr, Coin, StdError, StdResult, Uint128};
 
 use crate::denom::Denom;
 
+/// Kept with its original behaviour: a missing denom is `NotFound`, any other denom is
+/// `InvalidUtf8`, and zero or repeated coins of `denom` are accepted
+#[deprecated(note = "Use `Funds::exactly`, which reports the actual error")]
 pub fn amount(denom: &Denom, funds: Vec<Coin>) -> StdResult<Uint128> {
     let coin = funds
         .iter()
@@ -21,3 +24,29 @@ pub fn amount(denom: &Denom, funds: Vec<Coin>) -> StdResult<Uint128> {
 pub fn fee_address() -> Addr {
     Addr::unchecked("kujira17xpfvakm2amg962yls6f84z3kell8c5lp3pcxh")
 }
+
+#[cfg(test)]
+#[allow(deprecated)]
+mod tests {
+    use cosmwasm_std::coin;
+
+    use super::*;
+
+    #[test]
+    fn amount_legacy_errors() {
+        let denom = Denom::from("ukuji");
+        assert_eq!(amount(&denom, vec![coin(0, "ukuji")]), Ok(Uint128::zero()));
+        assert_eq!(
+            amount(&denom, vec![coin(5, "ukuji"), coin(7, "ukuji")]),
+            Ok(Uint128::new(5))
+        );
+        assert!(matches!(
+            amount(&denom, vec![coin(5, "uusk")]),
+            Err(StdError::NotFound { .. })
+        ));
+        assert!(matches!(
+            amount(&denom, vec![coin(5, "ukuji"), coin(5, "uusk")]),
+            Err(StdError::InvalidUtf8 { .. })
+        ));
+    }
+}